@@ -0,0 +1,5 @@
+use seasons_io::SeasonsMetadata;
+
+fn main() {
+    gear_wasm_builder::build_with_metadata::<SeasonsMetadata>();
+}
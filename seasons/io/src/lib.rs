@@ -0,0 +1,133 @@
+#![no_std]
+use gmeta::{InOut, Metadata};
+use gstd::{prelude::*, ActorId, Debug, Decode, Encode, TypeInfo};
+use store_io::AttributeId;
+
+pub type SeasonId = u64;
+
+pub struct SeasonsMetadata;
+
+impl Metadata for SeasonsMetadata {
+    type Init = ();
+    type Handle = InOut<SeasonsAction, SeasonsEvent>;
+    type Signal = ();
+    type Reply = ();
+    type Others = ();
+    type State = SeasonsState;
+}
+
+#[derive(Default, Encode, Decode, TypeInfo, Debug)]
+pub struct SeasonsState {
+    pub admin: ActorId,
+    /// Events that haven't ended yet, ordered by start block.
+    pub events: Vec<SeasonalEvent>,
+    pub subscribers: BTreeSet<ActorId>,
+    pub next_id: SeasonId,
+}
+
+impl SeasonsState {
+    /// Drops the events that ended by `current_block_height`.
+    pub fn prune(&mut self, current_block_height: u64) {
+        self.events
+            .retain(|event| event.end_block > current_block_height);
+    }
+
+    /// Whether `start_block..end_block` overlaps an event already scheduled.
+    pub fn overlaps(&self, start_block: u64, end_block: u64) -> bool {
+        self.events
+            .iter()
+            .any(|event| event.overlap(start_block, end_block) > 0)
+    }
+}
+
+/// Percent each mood decays at compared to normal, so 200 doubles it, and
+/// XP given per equipped bonus attribute on every game.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
+pub struct Modifiers {
+    pub hunger_percent: u64,
+    pub boredom_percent: u64,
+    pub energy_percent: u64,
+    pub bonus_attributes: Vec<AttributeId>,
+    pub attribute_xp: u64,
+}
+
+impl Default for Modifiers {
+    fn default() -> Self {
+        Modifiers {
+            hunger_percent: 100,
+            boredom_percent: 100,
+            energy_percent: 100,
+            bonus_attributes: Vec::new(),
+            attribute_xp: 0,
+        }
+    }
+}
+
+/// A live-ops event applying `modifiers` from `start_block` until, but not
+/// including, `end_block`.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
+pub struct SeasonalEvent {
+    pub id: SeasonId,
+    pub name: String,
+    pub start_block: u64,
+    pub end_block: u64,
+    pub modifiers: Modifiers,
+}
+
+impl SeasonalEvent {
+    pub fn is_active(&self, block: u64) -> bool {
+        (self.start_block..self.end_block).contains(&block)
+    }
+
+    /// Blocks of `from..to` that fall inside the event.
+    pub fn overlap(&self, from: u64, to: u64) -> u64 {
+        to.min(self.end_block)
+            .saturating_sub(from.max(self.start_block))
+    }
+}
+
+/// Scales `decay` accumulated over `from..to` by the modifier `percent` of
+/// every event overlapping that window; blocks outside events stay at 100%.
+pub fn scale_decay(
+    events: &[SeasonalEvent],
+    from: u64,
+    to: u64,
+    decay: u64,
+    percent: fn(&Modifiers) -> u64,
+) -> u64 {
+    let elapsed = to.saturating_sub(from) as u128;
+    if elapsed == 0 || events.is_empty() {
+        return decay;
+    }
+    let weighted = events.iter().fold(elapsed * 100, |weighted, event| {
+        let overlap = event.overlap(from, to) as u128;
+        weighted.saturating_sub(overlap * 100) + overlap * percent(&event.modifiers) as u128
+    });
+    (decay as u128 * weighted / (elapsed * 100)) as u64
+}
+
+#[derive(Encode, Decode, TypeInfo, Debug, Clone)]
+pub enum SeasonsAction {
+    /// Admin only; events can't overlap each other.
+    AddEvent {
+        name: String,
+        start_block: u64,
+        end_block: u64,
+        modifiers: Modifiers,
+    },
+    CancelEvent(SeasonId),
+    /// Subscribes the sender, which gets the schedule in the reply and on
+    /// every change after that.
+    Subscribe,
+    Unsubscribe,
+    Events,
+}
+
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
+pub enum SeasonsEvent {
+    EventAdded(SeasonId),
+    EventCancelled(SeasonId),
+    Subscribed(Vec<SeasonalEvent>),
+    Unsubscribed,
+    Events(Vec<SeasonalEvent>),
+}
@@ -0,0 +1,88 @@
+#![no_std]
+use gstd::{exec, msg};
+use seasons_io::{SeasonalEvent, SeasonsAction, SeasonsEvent, SeasonsState};
+use tamagotchi_io::TmAction;
+
+static mut STATE: Option<SeasonsState> = None;
+
+#[no_mangle]
+extern "C" fn init() {
+    unsafe {
+        STATE = Some(SeasonsState {
+            admin: msg::source(),
+            ..Default::default()
+        });
+    }
+}
+
+#[no_mangle]
+extern "C" fn handle() {
+    let action: SeasonsAction = msg::load().expect("no action given");
+    let seasons = unsafe { STATE.as_mut().expect("seasons not initialized") };
+    let current_block_height = exec::block_height() as u64;
+    seasons.prune(current_block_height);
+    let event = match action {
+        SeasonsAction::AddEvent {
+            name,
+            start_block,
+            end_block,
+            modifiers,
+        } => {
+            assert_eq!(msg::source(), seasons.admin, "Only admin can add events");
+            assert!(
+                start_block < end_block && end_block > current_block_height,
+                "Event window is empty or already over"
+            );
+            assert!(
+                !seasons.overlaps(start_block, end_block),
+                "Event overlaps another one"
+            );
+            let id = seasons.next_id;
+            seasons.next_id += 1;
+            seasons.events.push(SeasonalEvent {
+                id,
+                name,
+                start_block,
+                end_block,
+                modifiers,
+            });
+            seasons.events.sort_by_key(|event| event.start_block);
+            notify_subscribers(seasons);
+            SeasonsEvent::EventAdded(id)
+        }
+        SeasonsAction::CancelEvent(id) => {
+            assert_eq!(msg::source(), seasons.admin, "Only admin can cancel events");
+            seasons.events.retain(|event| event.id != id);
+            notify_subscribers(seasons);
+            SeasonsEvent::EventCancelled(id)
+        }
+        SeasonsAction::Subscribe => {
+            seasons.subscribers.insert(msg::source());
+            SeasonsEvent::Subscribed(seasons.events.clone())
+        }
+        SeasonsAction::Unsubscribe => {
+            seasons.subscribers.remove(&msg::source());
+            SeasonsEvent::Unsubscribed
+        }
+        SeasonsAction::Events => SeasonsEvent::Events(seasons.events.clone()),
+    };
+    msg::reply(event, 0).expect("reply failed");
+}
+
+/// Pushes the schedule to every subscribed tamagotchi.
+fn notify_subscribers(seasons: &SeasonsState) {
+    for subscriber in seasons.subscribers.iter() {
+        msg::send(
+            *subscriber,
+            TmAction::SyncSeasons(seasons.events.clone()),
+            0,
+        )
+        .expect("Failed to notify subscriber");
+    }
+}
+
+#[no_mangle]
+extern "C" fn state() {
+    let seasons = unsafe { STATE.as_ref().expect("seasons not initialized") };
+    msg::reply(seasons, 0).expect("reply failed");
+}
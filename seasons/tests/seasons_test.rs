@@ -0,0 +1,61 @@
+use gtest::{Log, Program, System};
+use seasons_io::{scale_decay, Modifiers, SeasonalEvent, SeasonsAction, SeasonsEvent};
+use tamagotchi_io::TmAction;
+
+const ADMIN: u64 = 100;
+const PET: u64 = 101;
+const SEASONS: u64 = 1;
+
+#[test]
+fn seasons_schedule() {
+    let sys = System::new();
+    let program = Program::current_with_id(&sys, SEASONS);
+    let res = program.send_bytes(ADMIN, b"");
+    assert!(!res.main_failed());
+    let res = program.send(PET, SeasonsAction::Subscribe);
+    let log = Log::builder()
+        .dest(PET)
+        .payload(SeasonsEvent::Subscribed(vec![]));
+    assert!(res.contains(&log));
+    let halloween = Modifiers {
+        boredom_percent: 200,
+        bonus_attributes: vec![1],
+        attribute_xp: 5,
+        ..Default::default()
+    };
+    let add_event = |name: &str, start_block, end_block| SeasonsAction::AddEvent {
+        name: String::from(name),
+        start_block,
+        end_block,
+        modifiers: halloween.clone(),
+    };
+    //Only the admin schedules events
+    let res = program.send(PET, add_event("Halloween", 100, 200));
+    assert!(res.main_failed());
+    let res = program.send(ADMIN, add_event("Halloween", 100, 200));
+    assert!(!res.main_failed());
+    let event = SeasonalEvent {
+        id: 0,
+        name: String::from("Halloween"),
+        start_block: 100,
+        end_block: 200,
+        modifiers: halloween.clone(),
+    };
+    //Subscribers get the new schedule pushed to them
+    let log = Log::builder()
+        .dest(PET)
+        .payload(TmAction::SyncSeasons(vec![event.clone()]));
+    assert!(res.contains(&log));
+    let res = program.send(ADMIN, add_event("Day of the Dead", 150, 250));
+    assert!(res.main_failed());
+    //Boredom doubles only for the blocks inside the window
+    let events = [event];
+    assert_eq!(
+        scale_decay(&events, 0, 100, 100, |modifiers| modifiers.boredom_percent),
+        100
+    );
+    assert_eq!(
+        scale_decay(&events, 50, 150, 100, |modifiers| modifiers.boredom_percent),
+        150
+    );
+}
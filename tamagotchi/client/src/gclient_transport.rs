@@ -0,0 +1,58 @@
+use crate::{ClientError, Result, Transport};
+use async_trait::async_trait;
+use gclient::{EventProcessor, GearApi};
+use gear_core::ids::ProgramId;
+use parity_scale_codec::Encode;
+use tamagotchi_io::{TamagotchiState, TmAction};
+
+/// Sends to a program on a node, signed by the account `api` was built with.
+pub struct GclientTransport {
+    api: GearApi,
+    program_id: ProgramId,
+}
+
+impl GclientTransport {
+    pub fn new(api: GearApi, program_id: ProgramId) -> Self {
+        GclientTransport { api, program_id }
+    }
+
+    /// Connects to a local dev node as Alice.
+    pub async fn dev(program_id: ProgramId) -> Result<Self> {
+        let api = GearApi::dev().await.map_err(transport_error)?;
+        Ok(GclientTransport::new(api, program_id))
+    }
+}
+
+fn transport_error(error: gclient::Error) -> ClientError {
+    ClientError::Transport(error.to_string())
+}
+
+#[async_trait(?Send)]
+impl Transport for GclientTransport {
+    async fn send(&self, action: TmAction, value: u128) -> Result<Vec<u8>> {
+        let payload = action.encode();
+        let gas = self
+            .api
+            .calculate_handle_gas(None, self.program_id, payload.clone(), value, true)
+            .await
+            .map_err(transport_error)?;
+        let mut listener = self.api.subscribe().await.map_err(transport_error)?;
+        let (message_id, _) = self
+            .api
+            .send_message_bytes(self.program_id, payload, gas.min_limit, value)
+            .await
+            .map_err(transport_error)?;
+        let (_, reply, _) = listener
+            .reply_bytes_on(message_id)
+            .await
+            .map_err(transport_error)?;
+        reply.map_err(ClientError::Panicked)
+    }
+
+    async fn read_state(&self) -> Result<TamagotchiState> {
+        self.api
+            .read_state(self.program_id)
+            .await
+            .map_err(transport_error)
+    }
+}
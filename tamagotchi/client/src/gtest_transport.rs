@@ -0,0 +1,49 @@
+use crate::{ClientError, Result, Transport};
+use async_trait::async_trait;
+use gstd::ActorId;
+use gtest::Program;
+use tamagotchi_io::{TamagotchiState, TmAction};
+
+/// Sends to a program in a `gtest::System` on behalf of `sender`.
+pub struct GtestTransport<'a> {
+    program: &'a Program<'a>,
+    sender: ActorId,
+}
+
+impl<'a> GtestTransport<'a> {
+    pub fn new(program: &'a Program<'a>, sender: impl Into<ActorId>) -> Self {
+        GtestTransport {
+            program,
+            sender: sender.into(),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Transport for GtestTransport<'_> {
+    async fn send(&self, action: TmAction, value: u128) -> Result<Vec<u8>> {
+        let res = self.program.send_with_value(self.sender, action, value);
+        // Other messages to the sender, like alerts, can follow the reply
+        let message_id = res.sent_message_id();
+        let reply = res
+            .log()
+            .iter()
+            .find(|log| log.reply_to() == Some(message_id))
+            .map(|log| log.payload().to_vec());
+        match (res.main_failed(), reply) {
+            (false, Some(reply)) => Ok(reply),
+            (true, reply) => Err(ClientError::Panicked(
+                reply
+                    .map(|reply| String::from_utf8_lossy(&reply).into_owned())
+                    .unwrap_or_default(),
+            )),
+            (false, None) => Err(ClientError::Transport(String::from("No reply received"))),
+        }
+    }
+
+    async fn read_state(&self) -> Result<TamagotchiState> {
+        self.program
+            .read_state()
+            .map_err(|error| ClientError::Transport(format!("{:?}", error)))
+    }
+}
@@ -0,0 +1,382 @@
+//! Typed client for the tamagotchi program.
+//!
+//! [`TamagotchiClient`] builds the `TmAction` payloads and decodes the
+//! `TmEvent` replies, turning rejections and panics into [`ClientError`]. It
+//! talks to the program through a [`Transport`]: [`GtestTransport`] for
+//! tests and [`GclientTransport`] for a running node.
+//!
+//! Actions without a method of their own go through
+//! [`TamagotchiClient::call`]:
+//! - `Age`, `Unequip`, `AcceptVisit`, `Rename` and `SetProfile`;
+//! - `SetHousehold`, `Propose`, `ApproveProposal`, `Proposals`,
+//!   `SetRenameFee`, `ExportState`, `Migrate` and `Batch`;
+//! - `AllowStore`, `RemoveStore`, `AllowedStores`, `SetDelegateBudget` and
+//!   `DelegateBudget`;
+//! - `Pause`, `Unpause`, `ReserveGas`, `SetCheckInterval`, `StopChecks` and
+//!   `SubscribeSeasons`;
+//! - `CancelLoanOffer` and `CancelBuyout`.
+//!
+//! `CheckState`, `WakeUp`, `LoanDue`, `HostVisit` and `SyncSeasons` are only
+//! sent by programs, so the client leaves them out.
+
+use async_trait::async_trait;
+use gstd::ActorId;
+use parity_scale_codec::Decode;
+use std::fmt;
+use store_io::AttributeId;
+use tamagotchi_io::{
+    Game, GameResult, Household, LoanTerms, PetMetadata, Policy, Shares, TamagotchiState,
+    TamagotchiStats, TmAction, TmError, TmEvent, Wellbeing, COMMIT_STAKE,
+};
+
+#[cfg(feature = "gclient")]
+mod gclient_transport;
+#[cfg(feature = "gtest")]
+mod gtest_transport;
+
+#[cfg(feature = "gclient")]
+pub use gclient_transport::GclientTransport;
+#[cfg(feature = "gtest")]
+pub use gtest_transport::GtestTransport;
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// The message couldn't be sent or its reply couldn't be received.
+    Transport(String),
+    /// The program panicked while handling the message.
+    Panicked(String),
+    /// The program refused the action with a `TmEvent::Error` reply.
+    Rejected(TmError),
+    /// The store didn't sell the attribute.
+    PurchaseFailed,
+    /// The program approved nothing on the token contract.
+    ApprovalFailed,
+    Decode(parity_scale_codec::Error),
+    UnexpectedReply(TmEvent),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Transport(error) => write!(f, "transport error: {}", error),
+            ClientError::Panicked(message) => write!(f, "program panicked: {}", message),
+            ClientError::Rejected(error) => write!(f, "action rejected: {:?}", error),
+            ClientError::PurchaseFailed => write!(f, "attribute purchase failed"),
+            ClientError::ApprovalFailed => write!(f, "token approval failed"),
+            ClientError::Decode(error) => write!(f, "undecodable reply: {}", error),
+            ClientError::UnexpectedReply(event) => write!(f, "unexpected reply: {:?}", event),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<parity_scale_codec::Error> for ClientError {
+    fn from(error: parity_scale_codec::Error) -> Self {
+        ClientError::Decode(error)
+    }
+}
+
+pub type Result<T, E = ClientError> = std::result::Result<T, E>;
+
+/// Delivers encoded actions to one tamagotchi and returns its replies.
+#[async_trait(?Send)]
+pub trait Transport {
+    /// Sends `action` with `value` attached and returns the reply payload,
+    /// or `ClientError::Panicked` if the program failed.
+    async fn send(&self, action: TmAction, value: u128) -> Result<Vec<u8>>;
+
+    async fn read_state(&self) -> Result<TamagotchiState>;
+}
+
+pub struct TamagotchiClient<T> {
+    transport: T,
+}
+
+impl<T: Transport> TamagotchiClient<T> {
+    pub fn new(transport: T) -> Self {
+        TamagotchiClient { transport }
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Sends `action` and decodes the reply, turning `TmEvent::Error` into
+    /// `ClientError::Rejected`.
+    pub async fn call_with_value(&self, action: TmAction, value: u128) -> Result<TmEvent> {
+        let reply = self.transport.send(action, value).await?;
+        match TmEvent::decode(&mut reply.as_ref())? {
+            TmEvent::Error(error) => Err(ClientError::Rejected(error)),
+            event => Ok(event),
+        }
+    }
+
+    pub async fn call(&self, action: TmAction) -> Result<TmEvent> {
+        self.call_with_value(action, 0).await
+    }
+
+    /// Sends `action` and checks that it replied with `expected`. Methods
+    /// whose reply is known up front use this; the rest match on the reply
+    /// to pull their result out of it.
+    async fn expect(&self, action: TmAction, expected: TmEvent) -> Result<()> {
+        match self.call(action).await? {
+            event if event == expected => Ok(()),
+            event => Err(ClientError::UnexpectedReply(event)),
+        }
+    }
+
+    pub async fn state(&self) -> Result<TamagotchiState> {
+        self.transport.read_state().await
+    }
+
+    pub async fn name(&self) -> Result<String> {
+        match self.call(TmAction::Name).await? {
+            TmEvent::Name(name) => Ok(name),
+            event => Err(ClientError::UnexpectedReply(event)),
+        }
+    }
+
+    pub async fn feed(&self) -> Result<()> {
+        self.expect(TmAction::Feed, TmEvent::Fed).await
+    }
+
+    /// Plays `game`, returning its result unless it was a plain `Fetch`.
+    pub async fn play(&self, game: Game) -> Result<Option<GameResult>> {
+        match self.call(TmAction::Play(game)).await? {
+            TmEvent::Entertained => Ok(None),
+            TmEvent::Played(result) => Ok(Some(result)),
+            event => Err(ClientError::UnexpectedReply(event)),
+        }
+    }
+
+    pub async fn sleep(&self, blocks: u32) -> Result<()> {
+        self.expect(TmAction::Sleep { blocks }, TmEvent::Slept)
+            .await
+    }
+
+    pub async fn transfer(&self, new_owner: ActorId) -> Result<()> {
+        self.expect(TmAction::Transfer(new_owner), TmEvent::Transfer(new_owner))
+            .await
+    }
+
+    pub async fn approve(&self, account: ActorId) -> Result<()> {
+        self.expect(TmAction::Approve(account), TmEvent::Approve(account))
+            .await
+    }
+
+    pub async fn revoke_approval(&self) -> Result<()> {
+        self.expect(TmAction::RevokeApproval, TmEvent::RevokeApproval)
+            .await
+    }
+
+    pub async fn set_token_contract(&self, ft_contract: ActorId) -> Result<()> {
+        self.expect(
+            TmAction::SetTokenContract(ft_contract),
+            TmEvent::TokenContractSet,
+        )
+        .await
+    }
+
+    pub async fn approve_tokens(&self, account: ActorId, amount: u128) -> Result<()> {
+        match self
+            .call(TmAction::ApproveTokens { account, amount })
+            .await?
+        {
+            TmEvent::TokensApproved { .. } => Ok(()),
+            TmEvent::ApprovalError => Err(ClientError::ApprovalFailed),
+            event => Err(ClientError::UnexpectedReply(event)),
+        }
+    }
+
+    pub async fn buy_attribute(&self, store_id: ActorId, attribute_id: AttributeId) -> Result<()> {
+        match self
+            .call(TmAction::BuyAttribute {
+                store_id,
+                attribute_id,
+            })
+            .await?
+        {
+            TmEvent::AttributeBought(_) => Ok(()),
+            TmEvent::ErrorDuringPurchase => Err(ClientError::PurchaseFailed),
+            event => Err(ClientError::UnexpectedReply(event)),
+        }
+    }
+
+    pub async fn equip(&self, attribute_id: AttributeId) -> Result<()> {
+        self.expect(
+            TmAction::Equip(attribute_id),
+            TmEvent::Equipped(attribute_id),
+        )
+        .await
+    }
+
+    /// Visits `other_pet` and returns the new friendship score with it.
+    pub async fn visit(&self, other_pet: ActorId) -> Result<u64> {
+        match self.call(TmAction::Visit { other_pet }).await? {
+            TmEvent::Visited { friendship, .. } => Ok(friendship),
+            event => Err(ClientError::UnexpectedReply(event)),
+        }
+    }
+
+    pub async fn withdraw(&self) -> Result<u128> {
+        match self.call(TmAction::Withdraw).await? {
+            TmEvent::Withdrawn(amount) => Ok(amount),
+            event => Err(ClientError::UnexpectedReply(event)),
+        }
+    }
+
+    /// Opens a policy with `insurer` and returns the last block it covers.
+    pub async fn insure(&self, insurer: ActorId) -> Result<u64> {
+        match self.call(TmAction::Insure { insurer }).await? {
+            TmEvent::Insured { paid_until } => Ok(paid_until),
+            event => Err(ClientError::UnexpectedReply(event)),
+        }
+    }
+
+    pub async fn pay_premium(&self) -> Result<u64> {
+        match self.call(TmAction::PayPremium).await? {
+            TmEvent::PremiumPaid { paid_until } => Ok(paid_until),
+            event => Err(ClientError::UnexpectedReply(event)),
+        }
+    }
+
+    /// The current policy, if any, and the premium for the next period.
+    pub async fn insurance(&self) -> Result<(Option<Policy>, u128)> {
+        match self.call(TmAction::Insurance).await? {
+            TmEvent::Insurance { policy, premium } => Ok((policy, premium)),
+            event => Err(ClientError::UnexpectedReply(event)),
+        }
+    }
+
+    /// Offers a loan against the pet. The principal is pulled from the
+    /// caller's FT balance on acceptance, so approve the pet for it first.
+    pub async fn offer_loan(&self, terms: LoanTerms) -> Result<()> {
+        self.expect(TmAction::OfferLoan(terms), TmEvent::LoanOffered(terms))
+            .await
+    }
+
+    /// Takes `lender`'s offer and returns the last block to repay by.
+    pub async fn accept_loan(&self, lender: ActorId) -> Result<u64> {
+        match self.call(TmAction::AcceptLoan { lender }).await? {
+            TmEvent::LoanAccepted { due_block, .. } => Ok(due_block),
+            event => Err(ClientError::UnexpectedReply(event)),
+        }
+    }
+
+    pub async fn repay_loan(&self) -> Result<()> {
+        self.expect(TmAction::RepayLoan, TmEvent::LoanRepaid).await
+    }
+
+    /// Splits the pet into shares and returns how many there are.
+    pub async fn fractionalise(&self, holders: Vec<(ActorId, u64)>) -> Result<u64> {
+        match self.call(TmAction::Fractionalise(holders)).await? {
+            TmEvent::Fractionalised { total } => Ok(total),
+            event => Err(ClientError::UnexpectedReply(event)),
+        }
+    }
+
+    pub async fn transfer_shares(&self, to: ActorId, amount: u64) -> Result<()> {
+        self.expect(
+            TmAction::TransferShares { to, amount },
+            TmEvent::SharesTransferred { to, amount },
+        )
+        .await
+    }
+
+    /// Pays `amount` of the caller's FT out to the shareholders and returns
+    /// those who couldn't be paid, who can claim their cut later. Approve
+    /// the pet for `amount` first.
+    pub async fn distribute_revenue(&self, amount: u128) -> Result<Vec<ActorId>> {
+        match self.call(TmAction::DistributeRevenue(amount)).await? {
+            TmEvent::RevenueDistributed { unpaid, .. } => Ok(unpaid),
+            event => Err(ClientError::UnexpectedReply(event)),
+        }
+    }
+
+    pub async fn offer_buyout(&self, price_per_share: u128) -> Result<()> {
+        self.expect(
+            TmAction::OfferBuyout { price_per_share },
+            TmEvent::BuyoutOffered,
+        )
+        .await
+    }
+
+    /// Votes for the pending buyout; the reply is `BuyoutVoted` until the
+    /// vote passes and `BoughtOut` once it settles.
+    pub async fn vote_buyout(&self) -> Result<TmEvent> {
+        self.call(TmAction::VoteBuyout).await
+    }
+
+    /// Claims the FT failed share payouts left with the pet and returns how
+    /// much was sent.
+    pub async fn claim_tokens(&self) -> Result<u128> {
+        match self.call(TmAction::ClaimTokens).await? {
+            TmEvent::TokensClaimed(amount) => Ok(amount),
+            event => Err(ClientError::UnexpectedReply(event)),
+        }
+    }
+
+    pub async fn shares(&self) -> Result<Option<Shares>> {
+        match self.call(TmAction::Shares).await? {
+            TmEvent::Shares(shares) => Ok(shares),
+            event => Err(ClientError::UnexpectedReply(event)),
+        }
+    }
+
+    /// Commits to `hash` with `COMMIT_STAKE` attached and returns the block
+    /// the action must be revealed by.
+    pub async fn commit(&self, hash: [u8; 32]) -> Result<u64> {
+        match self
+            .call_with_value(TmAction::Commit { hash }, COMMIT_STAKE)
+            .await?
+        {
+            TmEvent::Committed { reveal_by } => Ok(reveal_by),
+            event => Err(ClientError::UnexpectedReply(event)),
+        }
+    }
+
+    /// Reveals a committed action and returns its reply.
+    pub async fn reveal(&self, action: TmAction, salt: [u8; 32]) -> Result<TmEvent> {
+        self.call(TmAction::Reveal {
+            payload: Box::new(action),
+            salt,
+        })
+        .await
+    }
+
+    pub async fn household(&self) -> Result<Household> {
+        match self.call(TmAction::Household).await? {
+            TmEvent::Household(household) => Ok(household),
+            event => Err(ClientError::UnexpectedReply(event)),
+        }
+    }
+
+    pub async fn stats(&self) -> Result<TamagotchiStats> {
+        match self.call(TmAction::Stats).await? {
+            TmEvent::Stats(stats) => Ok(stats),
+            event => Err(ClientError::UnexpectedReply(event)),
+        }
+    }
+
+    pub async fn metadata(&self) -> Result<PetMetadata> {
+        match self.call(TmAction::Metadata).await? {
+            TmEvent::Metadata(metadata) => Ok(metadata),
+            event => Err(ClientError::UnexpectedReply(event)),
+        }
+    }
+
+    pub async fn wellbeing(&self) -> Result<Wellbeing> {
+        match self.call(TmAction::Wellbeing).await? {
+            TmEvent::Wellbeing(wellbeing) => Ok(wellbeing),
+            event => Err(ClientError::UnexpectedReply(event)),
+        }
+    }
+
+    pub async fn friends(&self) -> Result<Vec<(ActorId, u64)>> {
+        match self.call(TmAction::Friends).await? {
+            TmEvent::Friends(friends) => Ok(friends),
+            event => Err(ClientError::UnexpectedReply(event)),
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! Off-chain index of tamagotchi history.
+//!
+//! Every pet sends a `PetEvent` to `ActorId::zero()` for each change to its
+//! state. The indexer reads those events as [`Record`]s, either from gtest
+//! logs or from an exported JSON lines file, and writes them into a SQLite
+//! [`Store`] that answers timeline, mood, owner and purchase queries.
+
+use gstd::ActorId;
+use std::fmt;
+use tamagotchi_io::PetEvent;
+
+mod source;
+mod store;
+
+#[cfg(feature = "gtest")]
+pub use source::from_logs;
+pub use source::{export_json, read_json, ExportedEvent};
+pub use store::{MoodRecord, OwnerRecord, Purchase, Store, TimelineEntry};
+
+#[derive(Debug)]
+pub enum IndexerError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// A pet id or payload in an export isn't valid hex of the right length.
+    Hex(String),
+    Decode(parity_scale_codec::Error),
+    Sqlite(rusqlite::Error),
+}
+
+impl fmt::Display for IndexerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexerError::Io(error) => write!(f, "io error: {}", error),
+            IndexerError::Json(error) => write!(f, "invalid export line: {}", error),
+            IndexerError::Hex(value) => write!(f, "invalid hex: {}", value),
+            IndexerError::Decode(error) => write!(f, "undecodable event: {}", error),
+            IndexerError::Sqlite(error) => write!(f, "store error: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for IndexerError {}
+
+impl From<std::io::Error> for IndexerError {
+    fn from(error: std::io::Error) -> Self {
+        IndexerError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for IndexerError {
+    fn from(error: serde_json::Error) -> Self {
+        IndexerError::Json(error)
+    }
+}
+
+impl From<parity_scale_codec::Error> for IndexerError {
+    fn from(error: parity_scale_codec::Error) -> Self {
+        IndexerError::Decode(error)
+    }
+}
+
+impl From<rusqlite::Error> for IndexerError {
+    fn from(error: rusqlite::Error) -> Self {
+        IndexerError::Sqlite(error)
+    }
+}
+
+pub type Result<T, E = IndexerError> = std::result::Result<T, E>;
+
+/// An event together with the pet that emitted it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub pet: ActorId,
+    pub event: PetEvent,
+}
+
+/// Formats an id the way exports and the store write it.
+pub fn pet_hex(pet: &ActorId) -> String {
+    format!("0x{}", hex::encode(pet.as_ref()))
+}
+
+/// Parses an id written by [`pet_hex`], with or without the `0x` prefix.
+pub fn parse_pet(value: &str) -> Result<ActorId> {
+    let bytes = decode_hex(value)?;
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| IndexerError::Hex(value.to_string()))?;
+    Ok(bytes.into())
+}
+
+fn decode_hex(value: &str) -> Result<Vec<u8>> {
+    hex::decode(value.trim_start_matches("0x")).map_err(|_| IndexerError::Hex(value.to_string()))
+}
@@ -0,0 +1,46 @@
+use std::{env, fs::File, io::BufReader, process};
+use tamagotchi_indexer::{parse_pet, read_json, Result, Store};
+
+const USAGE: &str = "usage:
+    tamagotchi-indexer ingest <events.jsonl> <store.sqlite>
+    tamagotchi-indexer pets <store.sqlite>
+    tamagotchi-indexer timeline <store.sqlite> <pet>";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args.as_slice() {
+        ["ingest", events, store] => ingest(events, store),
+        ["pets", store] => pets(store),
+        ["timeline", store, pet] => timeline(store, pet),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(error) = result {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+fn ingest(events: &str, store: &str) -> Result<()> {
+    let records = read_json(BufReader::new(File::open(events)?))?;
+    let count = Store::open(store)?.ingest(records)?;
+    println!("Indexed {} events", count);
+    Ok(())
+}
+
+fn pets(store: &str) -> Result<()> {
+    for (pet, name) in Store::open(store)?.pets()? {
+        println!("{} {}", tamagotchi_indexer::pet_hex(&pet), name);
+    }
+    Ok(())
+}
+
+fn timeline(store: &str, pet: &str) -> Result<()> {
+    for entry in Store::open(store)?.timeline(&parse_pet(pet)?)? {
+        println!("{:>10} {:<12} {}", entry.block, entry.kind, entry.detail);
+    }
+    Ok(())
+}
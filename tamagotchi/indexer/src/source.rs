@@ -0,0 +1,60 @@
+use crate::{decode_hex, parse_pet, pet_hex, Record, Result};
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+use tamagotchi_io::PetEvent;
+
+/// One line of an export: the emitting pet and the SCALE encoded
+/// `PetEvent`, both as `0x` prefixed hex.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ExportedEvent {
+    pub pet: String,
+    pub payload: String,
+}
+
+/// Reads an export written by [`export_json`], skipping blank lines.
+pub fn read_json(reader: impl BufRead) -> Result<Vec<Record>> {
+    let mut records = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let exported: ExportedEvent = serde_json::from_str(&line)?;
+        records.push(Record {
+            pet: parse_pet(&exported.pet)?,
+            event: PetEvent::decode(&mut decode_hex(&exported.payload)?.as_slice())?,
+        });
+    }
+    Ok(records)
+}
+
+/// Writes `records` as JSON lines that [`read_json`] reads back.
+pub fn export_json<'a>(
+    records: impl IntoIterator<Item = &'a Record>,
+    mut writer: impl Write,
+) -> Result<()> {
+    for record in records {
+        let exported = ExportedEvent {
+            pet: pet_hex(&record.pet),
+            payload: format!("0x{}", hex::encode(record.event.encode())),
+        };
+        serde_json::to_writer(&mut writer, &exported)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Picks the pet events out of gtest logs; replies and alerts are skipped.
+#[cfg(feature = "gtest")]
+pub fn from_logs<'a>(logs: impl IntoIterator<Item = &'a gtest::CoreLog>) -> Result<Vec<Record>> {
+    logs.into_iter()
+        .filter(|log| log.destination() == gstd::ActorId::zero())
+        .map(|log| {
+            Ok(Record {
+                pet: <[u8; 32]>::from(log.source()).into(),
+                event: PetEvent::decode(&mut log.payload())?,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,369 @@
+use crate::{parse_pet, pet_hex, Record, Result};
+use gstd::ActorId;
+use rusqlite::{params, Connection, Transaction};
+use std::path::Path;
+use store_io::AttributeId;
+use tamagotchi_io::{Household, Lifecycle, PetChange, SpendLimit};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS events (
+        id INTEGER PRIMARY KEY,
+        pet TEXT NOT NULL,
+        block INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        detail TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS events_by_pet ON events (pet, block);
+    CREATE TABLE IF NOT EXISTS pets (
+        pet TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        born_block INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS moods (
+        pet TEXT NOT NULL,
+        block INTEGER NOT NULL,
+        fed INTEGER NOT NULL,
+        entertained INTEGER NOT NULL,
+        rested INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS owners (
+        pet TEXT NOT NULL,
+        block INTEGER NOT NULL,
+        members TEXT NOT NULL,
+        threshold INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS purchases (
+        pet TEXT NOT NULL,
+        block INTEGER NOT NULL,
+        attribute_id INTEGER NOT NULL
+    );
+";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimelineEntry {
+    pub block: u64,
+    pub kind: String,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoodRecord {
+    pub block: u64,
+    pub fed: u64,
+    pub entertained: u64,
+    pub rested: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnerRecord {
+    pub block: u64,
+    pub household: Household,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Purchase {
+    pub block: u64,
+    pub attribute_id: AttributeId,
+}
+
+/// SQLite database of indexed events.
+///
+/// Every event lands in `events` for timelines; births, moods, household
+/// changes and purchases are also written to their own tables so they can be
+/// queried without parsing details.
+pub struct Store {
+    connection: Connection,
+}
+
+impl Store {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    pub fn in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Store { connection })
+    }
+
+    /// Writes `records` in one transaction and returns how many were indexed.
+    pub fn ingest(&mut self, records: impl IntoIterator<Item = Record>) -> Result<usize> {
+        let transaction = self.connection.transaction()?;
+        let mut count = 0;
+        for record in records {
+            insert(&transaction, &record)?;
+            count += 1;
+        }
+        transaction.commit()?;
+        Ok(count)
+    }
+
+    /// Pets seen so far, with their current names.
+    pub fn pets(&self) -> Result<Vec<(ActorId, String)>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT pet, name FROM pets ORDER BY born_block, pet")?;
+        let rows = statement.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        rows.map(|row| {
+            let (pet, name) = row?;
+            Ok((parse_pet(&pet)?, name))
+        })
+        .collect()
+    }
+
+    pub fn timeline(&self, pet: &ActorId) -> Result<Vec<TimelineEntry>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT block, kind, detail FROM events WHERE pet = ?1 ORDER BY block, id")?;
+        let rows = statement.query_map([pet_hex(pet)], |row| {
+            Ok(TimelineEntry {
+                block: row.get(0)?,
+                kind: row.get(1)?,
+                detail: row.get(2)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn moods(&self, pet: &ActorId) -> Result<Vec<MoodRecord>> {
+        let mut statement = self.connection.prepare(
+            "SELECT block, fed, entertained, rested FROM moods WHERE pet = ?1 ORDER BY block, rowid",
+        )?;
+        let rows = statement.query_map([pet_hex(pet)], |row| {
+            Ok(MoodRecord {
+                block: row.get(0)?,
+                fed: row.get(1)?,
+                entertained: row.get(2)?,
+                rested: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn owners(&self, pet: &ActorId) -> Result<Vec<OwnerRecord>> {
+        let mut statement = self.connection.prepare(
+            "SELECT block, members, threshold FROM owners WHERE pet = ?1 ORDER BY block, rowid",
+        )?;
+        let rows = statement.query_map([pet_hex(pet)], |row| {
+            Ok((
+                row.get::<_, u64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, u32>(2)?,
+            ))
+        })?;
+        rows.map(|row| {
+            let (block, members, threshold) = row?;
+            let members = members
+                .split(',')
+                .filter(|member| !member.is_empty())
+                .map(parse_pet)
+                .collect::<Result<_>>()?;
+            Ok(OwnerRecord {
+                block,
+                household: Household { members, threshold },
+            })
+        })
+        .collect()
+    }
+
+    pub fn purchases(&self, pet: &ActorId) -> Result<Vec<Purchase>> {
+        let mut statement = self.connection.prepare(
+            "SELECT block, attribute_id FROM purchases WHERE pet = ?1 ORDER BY block, rowid",
+        )?;
+        let rows = statement.query_map([pet_hex(pet)], |row| {
+            Ok(Purchase {
+                block: row.get(0)?,
+                attribute_id: row.get(1)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+fn insert(transaction: &Transaction, record: &Record) -> Result<()> {
+    let pet = pet_hex(&record.pet);
+    let block = record.event.block;
+    let (kind, detail) = describe(&record.event.change);
+    transaction.execute(
+        "INSERT INTO events (pet, block, kind, detail) VALUES (?1, ?2, ?3, ?4)",
+        params![pet, block, kind, detail],
+    )?;
+    match &record.event.change {
+        PetChange::Born { name, household } => {
+            transaction.execute(
+                "INSERT OR REPLACE INTO pets (pet, name, born_block) VALUES (?1, ?2, ?3)",
+                params![pet, name, block],
+            )?;
+            insert_owner(transaction, &pet, block, household)?;
+        }
+        PetChange::Renamed(name) => {
+            transaction.execute(
+                "UPDATE pets SET name = ?2 WHERE pet = ?1",
+                params![pet, name],
+            )?;
+        }
+        PetChange::Moods {
+            fed,
+            entertained,
+            rested,
+        } => {
+            transaction.execute(
+                "INSERT INTO moods (pet, block, fed, entertained, rested) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![pet, block, fed, entertained, rested],
+            )?;
+        }
+        PetChange::HouseholdChanged(household) => {
+            insert_owner(transaction, &pet, block, household)?;
+        }
+        PetChange::AttributeBought(attribute_id) => {
+            transaction.execute(
+                "INSERT INTO purchases (pet, block, attribute_id) VALUES (?1, ?2, ?3)",
+                params![pet, block, attribute_id],
+            )?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn insert_owner(
+    transaction: &Transaction,
+    pet: &str,
+    block: u64,
+    household: &Household,
+) -> Result<()> {
+    let members: Vec<String> = household.members.iter().map(pet_hex).collect();
+    transaction.execute(
+        "INSERT INTO owners (pet, block, members, threshold) VALUES (?1, ?2, ?3, ?4)",
+        params![pet, block, members.join(","), household.threshold],
+    )?;
+    Ok(())
+}
+
+/// Short kind and human readable detail of a change for the timeline.
+fn describe(change: &PetChange) -> (&'static str, String) {
+    let members = |household: &Household| {
+        let members: Vec<String> = household.members.iter().map(pet_hex).collect();
+        format!("{} of [{}]", household.threshold, members.join(", "))
+    };
+    match change {
+        PetChange::Born { name, household } => {
+            ("born", format!("{} owned by {}", name, members(household)))
+        }
+        PetChange::Moods {
+            fed,
+            entertained,
+            rested,
+        } => (
+            "moods",
+            format!("fed {} entertained {} rested {}", fed, entertained, rested),
+        ),
+        PetChange::HouseholdChanged(household) => ("household", members(household)),
+        PetChange::Renamed(name) => ("renamed", name.clone()),
+        PetChange::AttributeBought(attribute_id) => ("bought", attribute_id.to_string()),
+        PetChange::AttributeEquipped(attribute_id) => ("equipped", attribute_id.to_string()),
+        PetChange::AttributeUnequipped(attribute_id) => ("unequipped", attribute_id.to_string()),
+        PetChange::TokensSpent(amount) => ("tokens_spent", amount.to_string()),
+        PetChange::XpEarned(xp) => ("xp", xp.to_string()),
+        PetChange::Friendship {
+            other_pet,
+            friendship,
+        } => (
+            "friendship",
+            format!("{} with {}", friendship, pet_hex(other_pet)),
+        ),
+        PetChange::LifecycleChanged(lifecycle) => (
+            "lifecycle",
+            match lifecycle {
+                Lifecycle::Active => String::from("active"),
+                Lifecycle::Migrating => String::from("migrating"),
+                Lifecycle::Retired { successor } => format!("retired to {}", pet_hex(successor)),
+                Lifecycle::Dead { at } => format!("died at {}", at),
+            },
+        ),
+        PetChange::SharesChanged(holders) => {
+            let holders: Vec<String> = holders
+                .iter()
+                .map(|(holder, held)| format!("{} {}", held, pet_hex(holder)))
+                .collect();
+            ("shares", holders.join(", "))
+        }
+        PetChange::ProfileChanged(profile) => (
+            "profile",
+            format!(
+                "{} [{}] avatar {}",
+                profile.description,
+                profile.tags.join(", "),
+                profile.avatar_uri.as_deref().unwrap_or("none")
+            ),
+        ),
+        PetChange::AllowedAccountChanged(account) => ("allowed_account", optional(account)),
+        PetChange::PauseChanged(pause) => (
+            "pause",
+            match pause {
+                Some(pause) if pause.care_open => format!("since {}, care open", pause.since),
+                Some(pause) => format!("since {}", pause.since),
+                None => String::from("none"),
+            },
+        ),
+        PetChange::TokenContractChanged(contract) => ("token_contract", optional(contract)),
+        PetChange::StoresChanged(stores) => {
+            let stores: Vec<String> = stores
+                .iter()
+                .map(|(store, limit)| match limit {
+                    Some(limit) => format!("{} capped at {}", pet_hex(store), spend_limit(limit)),
+                    None => pet_hex(store),
+                })
+                .collect();
+            ("stores", stores.join(", "))
+        }
+        PetChange::DelegateBudgetsChanged(budgets) => {
+            let budgets: Vec<String> = budgets
+                .iter()
+                .map(|(delegate, limit)| format!("{} {}", spend_limit(limit), pet_hex(delegate)))
+                .collect();
+            ("delegate_budgets", budgets.join(", "))
+        }
+        PetChange::LoanChanged(loan) => (
+            "loan",
+            match loan {
+                Some((lender, terms, due_block)) => format!(
+                    "{} from {}, {} due by {}",
+                    terms.principal,
+                    pet_hex(lender),
+                    terms.repayment,
+                    due_block
+                ),
+                None => String::from("none"),
+            },
+        ),
+        PetChange::InsuranceChanged(policy) => (
+            "insurance",
+            match policy {
+                Some(policy) => format!(
+                    "by {} until {}, {} revivals left",
+                    pet_hex(&policy.insurer),
+                    policy.paid_until,
+                    policy.revivals_left
+                ),
+                None => String::from("none"),
+            },
+        ),
+        PetChange::Owed { account, amount } => {
+            ("owed", format!("{} to {}", amount, pet_hex(account)))
+        }
+    }
+}
+
+fn optional(account: &Option<ActorId>) -> String {
+    account.as_ref().map_or(String::from("none"), pet_hex)
+}
+
+fn spend_limit(limit: &SpendLimit) -> String {
+    format!("{} per {} blocks", limit.amount, limit.period)
+}
@@ -0,0 +1,228 @@
+use gstd::{ActorId, MessageId};
+use std::{fs, io::BufReader};
+use tamagotchi_indexer::{
+    export_json, pet_hex, read_json, MoodRecord, OwnerRecord, Purchase, Record, Store,
+};
+use tamagotchi_io::{
+    Budget, Household, Loan, LoanTerms, Pause, PetChange, PetEvent, Policy, Snapshot, SpendLimit,
+    TamagotchiState,
+};
+
+//Recorded from two pets: Armando is bought things, shared and retired,
+//Luna only plays and visits
+const FIXTURE: &str = "tests/fixtures/events.jsonl";
+const ARMANDO: u64 = 2;
+const LUNA: u64 = 3;
+const FERNANDO: u64 = 10;
+const LUIS: u64 = 11;
+const FT_MAIN: u64 = 12;
+const STORE: u64 = 13;
+
+fn indexed_fixture() -> Store {
+    let records = read_json(BufReader::new(
+        fs::File::open(FIXTURE).expect("Fixture is missing"),
+    ))
+    .expect("Fixture is unreadable");
+    let mut store = Store::in_memory().expect("Unable to create the store");
+    assert_eq!(store.ingest(records).expect("Unable to index"), 17);
+    store
+}
+
+#[test]
+fn indexer_pets() {
+    let store = indexed_fixture();
+    assert_eq!(
+        store.pets().expect("Unable to query pets"),
+        vec![
+            (ARMANDO.into(), String::from("Bruno")),
+            (LUNA.into(), String::from("Luna")),
+        ]
+    );
+}
+
+#[test]
+fn indexer_timeline() {
+    let store = indexed_fixture();
+    let armando = ARMANDO.into();
+    let timeline = store.timeline(&armando).expect("Unable to query timeline");
+    assert_eq!(timeline.len(), 13);
+    let kinds: Vec<&str> = timeline.iter().map(|entry| entry.kind.as_str()).collect();
+    assert_eq!(kinds.first(), Some(&"born"));
+    assert_eq!(kinds.last(), Some(&"lifecycle"));
+    assert!(timeline
+        .windows(2)
+        .all(|pair| pair[0].block <= pair[1].block));
+    assert_eq!(
+        store
+            .moods(&armando)
+            .expect("Unable to query moods")
+            .first(),
+        Some(&MoodRecord {
+            block: 12,
+            fed: 2000,
+            entertained: 1,
+            rested: 1,
+        })
+    );
+    assert_eq!(
+        store
+            .purchases(&armando)
+            .expect("Unable to query purchases"),
+        vec![Purchase {
+            block: 25,
+            attribute_id: 1,
+        }]
+    );
+    assert_eq!(
+        store.owners(&armando).expect("Unable to query owners"),
+        vec![
+            OwnerRecord {
+                block: 1,
+                household: Household::solo(FERNANDO.into()),
+            },
+            OwnerRecord {
+                block: 30,
+                household: Household {
+                    members: vec![FERNANDO.into(), LUIS.into()],
+                    threshold: 2,
+                },
+            },
+        ]
+    );
+    //Unknown pets have no history
+    let stranger: ActorId = 99.into();
+    assert!(store
+        .timeline(&stranger)
+        .expect("Unable to query")
+        .is_empty());
+}
+
+#[test]
+fn indexer_export_round_trip() {
+    let fixture = fs::read_to_string(FIXTURE).expect("Fixture is missing");
+    let records = read_json(fixture.as_bytes()).expect("Fixture is unreadable");
+    assert!(matches!(
+        records[0].event.change,
+        PetChange::Born { ref name, .. } if name == "Armando"
+    ));
+    let mut exported = Vec::new();
+    export_json(&records, &mut exported).expect("Unable to export");
+    assert_eq!(
+        String::from_utf8(exported).expect("Export isn't utf-8"),
+        fixture
+    );
+}
+
+#[test]
+fn indexer_store_on_disk() {
+    let path = std::env::temp_dir().join("tamagotchi_indexer_test.sqlite");
+    let _ = fs::remove_file(&path);
+    let records = read_json(BufReader::new(
+        fs::File::open(FIXTURE).expect("Fixture is missing"),
+    ))
+    .expect("Fixture is unreadable");
+    Store::open(&path)
+        .expect("Unable to create the store")
+        .ingest(records)
+        .expect("Unable to index");
+    let store = Store::open(&path).expect("Unable to reopen the store");
+    assert_eq!(
+        store
+            .timeline(&LUNA.into())
+            .expect("Unable to query timeline")
+            .len(),
+        4
+    );
+    fs::remove_file(&path).expect("Unable to clean up");
+}
+
+#[test]
+fn indexer_state_changes() {
+    let mut tamagotchi = TamagotchiState::default();
+    let before = Snapshot::of(&tamagotchi);
+    tamagotchi.profile.description = String::from("Likes naps");
+    tamagotchi.allowed_account = Some(LUIS.into());
+    tamagotchi.pause = Some(Pause {
+        since: 40,
+        care_open: true,
+    });
+    tamagotchi.ft_contract = Some(FT_MAIN.into());
+    let limit = SpendLimit {
+        amount: 300,
+        period: 100,
+    };
+    tamagotchi
+        .allowed_stores
+        .insert(STORE.into(), Some(Budget::new(limit, 40)));
+    tamagotchi
+        .delegate_budgets
+        .insert(LUIS.into(), Budget::new(limit, 40));
+    tamagotchi.loan = Some(Loan {
+        lender: LUIS.into(),
+        terms: LoanTerms {
+            principal: 1_000,
+            repayment: 1_100,
+            duration: 500,
+        },
+        due_block: 540,
+        due_check: MessageId::default(),
+        repaying: false,
+    });
+    tamagotchi.insurance = Some(Policy {
+        insurer: LUIS.into(),
+        paid_until: 14_440,
+        revivals_left: 3,
+    });
+    tamagotchi.owed.insert(FERNANDO.into(), 25);
+    let changes = before.changes(&Snapshot::of(&tamagotchi));
+    let mut store = Store::in_memory().expect("Unable to create the store");
+    store
+        .ingest(changes.into_iter().map(|change| Record {
+            pet: ARMANDO.into(),
+            event: PetEvent { block: 40, change },
+        }))
+        .expect("Unable to index");
+    let timeline: Vec<(String, String)> = store
+        .timeline(&ARMANDO.into())
+        .expect("Unable to query timeline")
+        .into_iter()
+        .map(|entry| (entry.kind, entry.detail))
+        .collect();
+    let luis = pet_hex(&LUIS.into());
+    let expected = [
+        ("profile", String::from("Likes naps [] avatar none")),
+        ("allowed_account", luis.clone()),
+        ("pause", String::from("since 40, care open")),
+        ("token_contract", pet_hex(&FT_MAIN.into())),
+        (
+            "stores",
+            format!("{} capped at 300 per 100 blocks", pet_hex(&STORE.into())),
+        ),
+        ("delegate_budgets", format!("300 per 100 blocks {}", luis)),
+        ("loan", format!("1000 from {}, 1100 due by 540", luis)),
+        (
+            "insurance",
+            format!("by {} until 14440, 3 revivals left", luis),
+        ),
+        ("owed", format!("25 to {}", pet_hex(&FERNANDO.into()))),
+    ]
+    .map(|(kind, detail)| (String::from(kind), detail));
+    assert_eq!(timeline, expected);
+    //Spending against a limit or starting a repayment changes nothing
+    //worth indexing, and a settled balance is reported as zero
+    let before = Snapshot::of(&tamagotchi);
+    if let Some(budget) = tamagotchi.delegate_budgets.get_mut(&LUIS.into()) {
+        assert!(budget.spend(100, 45));
+    }
+    if let Some(loan) = tamagotchi.loan.as_mut() {
+        loan.repaying = true;
+    }
+    tamagotchi.owed.clear();
+    assert_eq!(
+        before.changes(&Snapshot::of(&tamagotchi)),
+        vec![PetChange::Owed {
+            account: FERNANDO.into(),
+            amount: 0,
+        }]
+    );
+}
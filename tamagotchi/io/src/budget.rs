@@ -0,0 +1,68 @@
+use crate::TmError;
+use gstd::{Debug, Decode, Encode, TypeInfo};
+
+/// Maximum `amount` that may be spent every `period` blocks.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SpendLimit {
+    pub amount: u128,
+    pub period: u64,
+}
+
+impl SpendLimit {
+    pub fn validate(&self) -> Result<(), TmError> {
+        if self.period == 0 {
+            return Err(TmError::InvalidPeriod);
+        }
+        Ok(())
+    }
+}
+
+/// Spend tracked against a `SpendLimit`, reset at the start of every period.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
+pub struct Budget {
+    pub limit: SpendLimit,
+    pub period_start: u64,
+    pub spent: u128,
+}
+
+impl Budget {
+    /// Starts tracking spend against `limit`, which must have been validated.
+    pub fn new(limit: SpendLimit, current_block_height: u64) -> Self {
+        assert!(limit.period > 0, "Budget period must be at least one block");
+        Budget {
+            limit,
+            period_start: current_block_height,
+            spent: 0,
+        }
+    }
+
+    fn current_period_start(&self, current_block_height: u64) -> u64 {
+        let elapsed = current_block_height.saturating_sub(self.period_start);
+        self.period_start + elapsed - elapsed % self.limit.period
+    }
+
+    pub fn remaining(&self, current_block_height: u64) -> u128 {
+        if self.current_period_start(current_block_height) != self.period_start {
+            return self.limit.amount;
+        }
+        self.limit.amount.saturating_sub(self.spent)
+    }
+
+    /// Records `amount` as spent, or returns `false` if it would exceed the limit.
+    pub fn spend(&mut self, amount: u128, current_block_height: u64) -> bool {
+        let period_start = self.current_period_start(current_block_height);
+        if period_start != self.period_start {
+            self.period_start = period_start;
+            self.spent = 0;
+        }
+        if amount > self.limit.amount.saturating_sub(self.spent) {
+            return false;
+        }
+        self.spent += amount;
+        true
+    }
+
+    pub fn refund(&mut self, amount: u128) {
+        self.spent = self.spent.saturating_sub(amount);
+    }
+}
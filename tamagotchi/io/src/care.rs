@@ -0,0 +1,85 @@
+use crate::{TmError, FILL_PER_ENTERTAINMENT, FILL_PER_FEED};
+use gstd::{Debug, Decode, Encode, TypeInfo};
+
+/// How much a care action fills its mood when it's repeated.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct CareRule {
+    pub fill: u64,
+    /// Repeats within this many blocks of the previous one give less.
+    pub window: u64,
+    /// Percent of the previous fill kept on every repeat inside the window.
+    pub repeat_percent: u64,
+    pub min_fill: u64,
+    /// Blocks that must pass before the action can be done again.
+    pub cooldown: u64,
+}
+
+impl CareRule {
+    pub fn fill_after(&self, repeats: u32) -> u64 {
+        let mut fill = self.fill;
+        for _ in 0..repeats {
+            fill = fill * self.repeat_percent / 100;
+            if fill <= self.min_fill {
+                return self.min_fill;
+            }
+        }
+        fill
+    }
+}
+
+/// Feeding past `threshold` costs the pet `penalty` entertainment.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct OverfeedRule {
+    pub threshold: u64,
+    pub penalty: u64,
+}
+
+pub const FEED_RULE: CareRule = CareRule {
+    fill: FILL_PER_FEED,
+    window: 100,
+    repeat_percent: 50,
+    min_fill: 50,
+    cooldown: 1,
+};
+
+pub const PLAY_RULE: CareRule = CareRule {
+    fill: FILL_PER_ENTERTAINMENT,
+    window: 60,
+    repeat_percent: 60,
+    min_fill: 50,
+    cooldown: 1,
+};
+
+pub const OVERFEED_RULE: OverfeedRule = OverfeedRule {
+    threshold: 9000,
+    penalty: 500,
+};
+
+/// When a care action was last done and how many times in a row it was
+/// repeated inside its rule's window.
+#[derive(Default, Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
+pub struct CareHistory {
+    pub last_block: Option<u64>,
+    pub repeats: u32,
+}
+
+impl CareHistory {
+    /// Records the action at `current_block_height` and returns how much it fills.
+    pub fn record(&mut self, rule: &CareRule, current_block_height: u64) -> Result<u64, TmError> {
+        if let Some(last_block) = self.last_block {
+            let since = current_block_height.saturating_sub(last_block);
+            if since < rule.cooldown {
+                return Err(TmError::OnCooldown {
+                    ready_at: last_block + rule.cooldown,
+                });
+            }
+            self.repeats = if since < rule.window {
+                self.repeats.saturating_add(1)
+            } else {
+                0
+            };
+        }
+        self.last_block = Some(current_block_height);
+        Ok(rule.fill_after(self.repeats))
+    }
+}
@@ -0,0 +1,29 @@
+use crate::TmAction;
+use blake2::{digest::consts::U32, Blake2b, Digest};
+use gstd::{ActorId, Debug, Decode, Encode, TypeInfo};
+
+/// Blocks after a commit in which it must be revealed.
+pub const REVEAL_BLOCKS: u64 = 100;
+/// Native value held from the committer until the reveal. It goes to the
+/// household instead if the deadline passes without one.
+pub const COMMIT_STAKE: u128 = 1_000;
+
+/// An action an account committed to without showing it yet.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Commitment {
+    pub hash: [u8; 32],
+    pub reveal_by: u64,
+    pub stake: u128,
+}
+
+/// Hash committed to before revealing `payload` with `salt`.
+///
+/// The committer is part of it, so nobody can replay another account's
+/// commitment as their own.
+pub fn commitment_hash(committer: &ActorId, payload: &TmAction, salt: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Blake2b::<U32>::new();
+    hasher.update(committer.as_ref());
+    hasher.update(payload.encode());
+    hasher.update(salt);
+    hasher.finalize().into()
+}
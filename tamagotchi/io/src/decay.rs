@@ -0,0 +1,136 @@
+use crate::{
+    BOREDOM_PER_BLOCK, ENERGY_PER_BLOCK, HUNGER_PER_BLOCK, MAX_MOOD_VALUE, MOOD_LIMIT,
+    REST_PER_SLEEP_BLOCK, SLEEP_HUNGER_DIVISOR,
+};
+use gstd::{prelude::*, Debug, Decode, Encode, TypeInfo};
+use seasons_io::{Modifiers, SeasonalEvent};
+
+/// Decay is counted in these fractions of a mood point, fine enough for the
+/// sleeping hunger rate under any whole seasonal percent.
+pub const DECAY_UNIT: u64 = 100 * SLEEP_HUNGER_DIVISOR;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Mood {
+    Hunger,
+    Boredom,
+    Energy,
+}
+
+/// Decay each mood has accrued short of a whole point, in `DECAY_UNIT`ths,
+/// carried over so that it doesn't matter how often the moods are updated.
+#[derive(Default, Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
+pub struct DecayRemainder {
+    pub hunger: u64,
+    pub boredom: u64,
+    pub energy: u64,
+}
+
+/// A run of blocks over which a mood decays at the same rate.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Span {
+    pub blocks: u64,
+    pub asleep: bool,
+    /// `DECAY_UNIT`ths of a point lost per block.
+    pub rate: u64,
+}
+
+impl Mood {
+    fn percent(self, modifiers: &Modifiers) -> u64 {
+        match self {
+            Mood::Hunger => modifiers.hunger_percent,
+            Mood::Boredom => modifiers.boredom_percent,
+            Mood::Energy => modifiers.energy_percent,
+        }
+    }
+
+    /// Rate at `percent` of normal; hunger slows down while the pet sleeps
+    /// and energy stops draining altogether.
+    fn rate(self, percent: u64, asleep: bool) -> u64 {
+        let per_block = match self {
+            Mood::Hunger => HUNGER_PER_BLOCK,
+            Mood::Boredom => BOREDOM_PER_BLOCK,
+            Mood::Energy => ENERGY_PER_BLOCK,
+        };
+        let awake = per_block
+            .saturating_mul(percent)
+            .saturating_mul(DECAY_UNIT / 100);
+        match (self, asleep) {
+            (Mood::Hunger, true) => awake / SLEEP_HUNGER_DIVISOR,
+            (Mood::Energy, true) => 0,
+            _ => awake,
+        }
+    }
+
+    /// Splits `from..to` wherever the pet wakes up or a seasonal event
+    /// starts or ends, the pet sleeping until `asleep_until`.
+    pub fn spans(
+        self,
+        events: &[SeasonalEvent],
+        from: u64,
+        to: u64,
+        asleep_until: Option<u64>,
+    ) -> Vec<Span> {
+        let inside = |block: u64| block.clamp(from, to.max(from));
+        let mut bounds: Vec<u64> = events
+            .iter()
+            .flat_map(|event| [event.start_block, event.end_block])
+            .chain(asleep_until)
+            .chain([from, to])
+            .map(inside)
+            .collect();
+        bounds.sort_unstable();
+        bounds.dedup();
+        bounds
+            .windows(2)
+            .map(|window| {
+                let asleep = asleep_until.is_some_and(|until| window[0] < until);
+                let percent = events
+                    .iter()
+                    .find(|event| event.is_active(window[0]))
+                    .map_or(100, |event| self.percent(&event.modifiers));
+                Span {
+                    blocks: window[1] - window[0],
+                    asleep,
+                    rate: self.rate(percent, asleep),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Whole points lost over `spans` on top of the carried `remainder`, which
+/// is left holding the fraction still short of a point.
+pub fn points(spans: &[Span], remainder: &mut u64) -> u64 {
+    let total = spans
+        .iter()
+        .map(|span| span.blocks.saturating_mul(span.rate))
+        .fold(*remainder, u64::saturating_add);
+    *remainder = total % DECAY_UNIT;
+    total / DECAY_UNIT
+}
+
+/// Blocks into `spans` that `mood` of the given value, already `remainder`
+/// on its way to the next point, stays above `MOOD_LIMIT`, or `u64::MAX` if
+/// it outlasts them. Energy recovers while the pet sleeps, so a rested pet
+/// stays above the limit for the whole nap.
+pub fn blocks_above_limit(mood: Mood, value: u64, remainder: u64, spans: &[Span]) -> u64 {
+    if value <= MOOD_LIMIT {
+        return 0;
+    }
+    let mut level = value * DECAY_UNIT - remainder;
+    let mut healthy = 0;
+    for span in spans {
+        if mood == Mood::Energy && span.asleep {
+            level = (level + REST_PER_SLEEP_BLOCK * DECAY_UNIT * span.blocks)
+                .min(MAX_MOOD_VALUE * DECAY_UNIT);
+        } else if let Some(blocks) = (level - MOOD_LIMIT * DECAY_UNIT - 1).checked_div(span.rate) {
+            // Blocks the mood can decay for and still be above the limit
+            if blocks < span.blocks {
+                return healthy + blocks;
+            }
+            level -= span.blocks * span.rate;
+        }
+        healthy += span.blocks;
+    }
+    u64::MAX
+}
@@ -0,0 +1,127 @@
+use crate::TmError;
+use gstd::{Debug, Decode, Encode, TypeInfo};
+
+/// Numbers in guess-the-number go from 1 to this.
+pub const GUESS_RANGE: u8 = 10;
+
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Hand {
+    Rock,
+    Paper,
+    Scissors,
+}
+
+impl Hand {
+    fn from_random(byte: u8) -> Self {
+        match byte % 3 {
+            0 => Hand::Rock,
+            1 => Hand::Paper,
+            _ => Hand::Scissors,
+        }
+    }
+
+    fn beats(self, other: Hand) -> bool {
+        matches!(
+            (self, other),
+            (Hand::Rock, Hand::Scissors)
+                | (Hand::Paper, Hand::Rock)
+                | (Hand::Scissors, Hand::Paper)
+        )
+    }
+}
+
+/// A game together with the move made in it.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Game {
+    /// Plain play without a winner.
+    Fetch,
+    RockPaperScissors(Hand),
+    GuessTheNumber(u8),
+}
+
+impl Game {
+    pub fn validate(&self) -> Result<(), TmError> {
+        match self {
+            Game::GuessTheNumber(guess) if *guess == 0 || *guess > GUESS_RANGE => {
+                Err(TmError::InvalidMove)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Makes the pet's move from `random` and returns it with the player's outcome.
+    pub fn play(&self, random: [u8; 32]) -> (Game, Outcome) {
+        match *self {
+            Game::Fetch => (Game::Fetch, Outcome::Draw),
+            Game::RockPaperScissors(hand) => {
+                let pet = Hand::from_random(random[0]);
+                let outcome = if hand.beats(pet) {
+                    Outcome::Win
+                } else if pet.beats(hand) {
+                    Outcome::Loss
+                } else {
+                    Outcome::Draw
+                };
+                (Game::RockPaperScissors(pet), outcome)
+            }
+            Game::GuessTheNumber(guess) => {
+                let secret = random[0] % GUESS_RANGE + 1;
+                let outcome = if guess == secret {
+                    Outcome::Win
+                } else {
+                    Outcome::Loss
+                };
+                (Game::GuessTheNumber(secret), outcome)
+            }
+        }
+    }
+}
+
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Outcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+/// Percent of the play fill and the XP an outcome earns.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Reward {
+    pub fill_percent: u64,
+    pub xp: u64,
+}
+
+impl Outcome {
+    pub fn reward(&self) -> Reward {
+        match self {
+            Outcome::Win => WIN_REWARD,
+            Outcome::Draw => DRAW_REWARD,
+            Outcome::Loss => LOSS_REWARD,
+        }
+    }
+}
+
+pub const WIN_REWARD: Reward = Reward {
+    fill_percent: 150,
+    xp: 10,
+};
+
+pub const DRAW_REWARD: Reward = Reward {
+    fill_percent: 100,
+    xp: 3,
+};
+
+pub const LOSS_REWARD: Reward = Reward {
+    fill_percent: 50,
+    xp: 1,
+};
+
+/// What happened in a game, for the client to animate.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct GameResult {
+    pub player: Game,
+    pub pet: Game,
+    pub outcome: Outcome,
+    pub entertainment: u64,
+    pub xp: u64,
+}
@@ -0,0 +1,233 @@
+use crate::{Household, Lifecycle, LoanTerms, Pause, Policy, Profile, SpendLimit, TamagotchiState};
+use gstd::{prelude::*, ActorId, Debug, Decode, Encode, TypeInfo};
+use store_io::AttributeId;
+
+/// A change to the pet, emitted to `ActorId::zero()` so indexers can follow
+/// its history from the message log alone.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
+pub struct PetEvent {
+    pub block: u64,
+    pub change: PetChange,
+}
+
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
+pub enum PetChange {
+    Born {
+        name: String,
+        household: Household,
+    },
+    Moods {
+        fed: u64,
+        entertained: u64,
+        rested: u64,
+    },
+    HouseholdChanged(Household),
+    Renamed(String),
+    AttributeBought(AttributeId),
+    AttributeEquipped(AttributeId),
+    AttributeUnequipped(AttributeId),
+    /// Tokens approved to spenders by this message.
+    TokensSpent(u128),
+    XpEarned(u64),
+    Friendship {
+        other_pet: ActorId,
+        friendship: u64,
+    },
+    LifecycleChanged(Lifecycle),
+    /// Who holds the pet's shares; empty once it's whole again.
+    SharesChanged(Vec<(ActorId, u64)>),
+    ProfileChanged(Profile),
+    AllowedAccountChanged(Option<ActorId>),
+    PauseChanged(Option<Pause>),
+    TokenContractChanged(Option<ActorId>),
+    /// Every allowed store with its spend limit, if it has one.
+    StoresChanged(Vec<(ActorId, Option<SpendLimit>)>),
+    /// Every delegate with a budget and its limit.
+    DelegateBudgetsChanged(Vec<(ActorId, SpendLimit)>),
+    /// Lender, terms and last block to repay in; `None` once settled.
+    LoanChanged(Option<(ActorId, LoanTerms, u64)>),
+    InsuranceChanged(Option<Policy>),
+    /// Native value `account` can withdraw, zero once it's all taken.
+    Owed {
+        account: ActorId,
+        amount: u128,
+    },
+}
+
+/// The parts of the state that `PetChange`s describe, taken before a message
+/// runs and compared with the state after it.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    moods: (u64, u64, u64),
+    household: Household,
+    name: String,
+    attributes: BTreeSet<AttributeId>,
+    equipped: BTreeSet<AttributeId>,
+    ft_spent: u128,
+    xp: u64,
+    friends: BTreeMap<ActorId, u64>,
+    lifecycle: Lifecycle,
+    shares: BTreeMap<ActorId, u64>,
+    profile: Profile,
+    allowed_account: Option<ActorId>,
+    pause: Option<Pause>,
+    ft_contract: Option<ActorId>,
+    stores: BTreeMap<ActorId, Option<SpendLimit>>,
+    delegate_budgets: BTreeMap<ActorId, SpendLimit>,
+    loan: Option<(ActorId, LoanTerms, u64)>,
+    insurance: Option<Policy>,
+    owed: BTreeMap<ActorId, u128>,
+}
+
+impl Snapshot {
+    pub fn of(tamagotchi: &TamagotchiState) -> Self {
+        Snapshot {
+            moods: (tamagotchi.fed, tamagotchi.entertained, tamagotchi.rested),
+            household: tamagotchi.household.clone(),
+            name: tamagotchi.name.clone(),
+            attributes: tamagotchi.attributes.clone(),
+            equipped: tamagotchi.equipped.clone(),
+            ft_spent: tamagotchi.stats.ft_spent,
+            xp: tamagotchi.stats.xp,
+            friends: tamagotchi.friends.clone(),
+            lifecycle: tamagotchi.lifecycle,
+            shares: tamagotchi
+                .shares
+                .as_ref()
+                .map(|shares| shares.holders.clone())
+                .unwrap_or_default(),
+            profile: tamagotchi.profile.clone(),
+            allowed_account: tamagotchi.allowed_account,
+            pause: tamagotchi.pause,
+            ft_contract: tamagotchi.ft_contract,
+            // Limits only, so spending against them isn't a change
+            stores: tamagotchi
+                .allowed_stores
+                .iter()
+                .map(|(store, budget)| (*store, budget.as_ref().map(|budget| budget.limit)))
+                .collect(),
+            delegate_budgets: tamagotchi
+                .delegate_budgets
+                .iter()
+                .map(|(delegate, budget)| (*delegate, budget.limit))
+                .collect(),
+            loan: tamagotchi
+                .loan
+                .as_ref()
+                .map(|loan| (loan.lender, loan.terms, loan.due_block)),
+            insurance: tamagotchi.insurance,
+            owed: tamagotchi.owed.clone(),
+        }
+    }
+
+    /// Everything that differs in `after`, in a fixed order.
+    pub fn changes(&self, after: &Snapshot) -> Vec<PetChange> {
+        let mut changes = Vec::new();
+        if self.moods != after.moods {
+            let (fed, entertained, rested) = after.moods;
+            changes.push(PetChange::Moods {
+                fed,
+                entertained,
+                rested,
+            });
+        }
+        if self.household != after.household {
+            changes.push(PetChange::HouseholdChanged(after.household.clone()));
+        }
+        if self.name != after.name {
+            changes.push(PetChange::Renamed(after.name.clone()));
+        }
+        changes.extend(
+            after
+                .attributes
+                .difference(&self.attributes)
+                .map(|id| PetChange::AttributeBought(*id)),
+        );
+        changes.extend(
+            after
+                .equipped
+                .difference(&self.equipped)
+                .map(|id| PetChange::AttributeEquipped(*id)),
+        );
+        changes.extend(
+            self.equipped
+                .difference(&after.equipped)
+                .map(|id| PetChange::AttributeUnequipped(*id)),
+        );
+        if after.ft_spent > self.ft_spent {
+            changes.push(PetChange::TokensSpent(after.ft_spent - self.ft_spent));
+        }
+        if after.xp > self.xp {
+            changes.push(PetChange::XpEarned(after.xp - self.xp));
+        }
+        changes.extend(
+            after
+                .friends
+                .iter()
+                .filter(|(other_pet, friendship)| self.friends.get(other_pet) != Some(friendship))
+                .map(|(other_pet, friendship)| PetChange::Friendship {
+                    other_pet: *other_pet,
+                    friendship: *friendship,
+                }),
+        );
+        if self.lifecycle != after.lifecycle {
+            changes.push(PetChange::LifecycleChanged(after.lifecycle));
+        }
+        if self.shares != after.shares {
+            changes.push(PetChange::SharesChanged(
+                after
+                    .shares
+                    .iter()
+                    .map(|(holder, held)| (*holder, *held))
+                    .collect(),
+            ));
+        }
+        if self.profile != after.profile {
+            changes.push(PetChange::ProfileChanged(after.profile.clone()));
+        }
+        if self.allowed_account != after.allowed_account {
+            changes.push(PetChange::AllowedAccountChanged(after.allowed_account));
+        }
+        if self.pause != after.pause {
+            changes.push(PetChange::PauseChanged(after.pause));
+        }
+        if self.ft_contract != after.ft_contract {
+            changes.push(PetChange::TokenContractChanged(after.ft_contract));
+        }
+        if self.stores != after.stores {
+            changes.push(PetChange::StoresChanged(
+                after
+                    .stores
+                    .iter()
+                    .map(|(store, limit)| (*store, *limit))
+                    .collect(),
+            ));
+        }
+        if self.delegate_budgets != after.delegate_budgets {
+            changes.push(PetChange::DelegateBudgetsChanged(
+                after
+                    .delegate_budgets
+                    .iter()
+                    .map(|(delegate, limit)| (*delegate, *limit))
+                    .collect(),
+            ));
+        }
+        if self.loan != after.loan {
+            changes.push(PetChange::LoanChanged(after.loan));
+        }
+        if self.insurance != after.insurance {
+            changes.push(PetChange::InsuranceChanged(after.insurance));
+        }
+        let accounts: BTreeSet<&ActorId> = self.owed.keys().chain(after.owed.keys()).collect();
+        changes.extend(accounts.into_iter().filter_map(|account| {
+            let amount = after.owed.get(account).copied().unwrap_or_default();
+            (self.owed.get(account).copied().unwrap_or_default() != amount).then_some(
+                PetChange::Owed {
+                    account: *account,
+                    amount,
+                },
+            )
+        }));
+        changes
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{TmAction, TmError};
+use gstd::{prelude::*, ActorId, Debug, Decode, Encode, TypeInfo};
+
+pub type ProposalId = u64;
+
+/// Members sharing a pet; `threshold` of them must approve a proposal.
+///
+/// A household of one with a threshold of one is a plain single owner, who
+/// can act directly without proposals.
+#[derive(Default, Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
+pub struct Household {
+    /// The first member receives the pet's alerts and native earnings.
+    pub members: Vec<ActorId>,
+    pub threshold: u32,
+}
+
+impl Household {
+    pub fn solo(owner: ActorId) -> Self {
+        Household {
+            members: vec![owner],
+            threshold: 1,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), TmError> {
+        let unique: BTreeSet<_> = self.members.iter().collect();
+        if unique.len() != self.members.len()
+            || self.threshold == 0
+            || self.threshold as usize > self.members.len()
+        {
+            return Err(TmError::InvalidHousehold);
+        }
+        Ok(())
+    }
+
+    pub fn is_member(&self, account: ActorId) -> bool {
+        self.members.contains(&account)
+    }
+
+    pub fn contact(&self) -> ActorId {
+        self.members.first().copied().unwrap_or_default()
+    }
+
+    /// Whether guarded actions have to go through proposals.
+    pub fn needs_proposals(&self) -> bool {
+        self.threshold > 1
+    }
+}
+
+/// A guarded action waiting for enough members to approve it.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
+pub struct Proposal {
+    pub action: TmAction,
+    pub approvals: BTreeSet<ActorId>,
+    pub expires_at: u64,
+}
+
+/// Actions a household has to agree on.
+pub fn is_guarded(action: &TmAction) -> bool {
+    matches!(
+        action,
+        TmAction::Transfer(_)
+            | TmAction::SetHousehold(_)
+            | TmAction::ApproveTokens { .. }
+            | TmAction::Fractionalise(_)
+            | TmAction::SetRenameFee(_)
+            | TmAction::SetTokenContract(_)
+            | TmAction::Migrate { .. }
+            | TmAction::Insure { .. }
+            | TmAction::AcceptLoan { .. }
+    )
+}
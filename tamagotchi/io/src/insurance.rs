@@ -0,0 +1,38 @@
+use gstd::{ActorId, Debug, Decode, Encode, TypeInfo};
+
+/// Blocks of unbroken neglect after which the pet dies.
+pub const DEATH_NEGLECT_BLOCKS: u64 = 14_400;
+/// Every mood is set to this when an insured pet is revived.
+pub const REVIVAL_MOOD: u64 = 2_000;
+/// Blocks one premium payment keeps the policy active for.
+pub const PREMIUM_PERIOD: u64 = 14_400;
+pub const BASE_PREMIUM: u128 = 100;
+/// Added to the premium for every `SURCHARGE_NEGLECT_BLOCKS` the pet has
+/// been neglected over its life.
+pub const NEGLECT_SURCHARGE: u128 = 10;
+pub const SURCHARGE_NEGLECT_BLOCKS: u64 = 600;
+pub const MAX_PREMIUM: u128 = 1_000;
+/// Free revivals a new policy comes with.
+pub const POLICY_REVIVALS: u32 = 3;
+
+/// Insurance the household pays `insurer` for, in FT, to have the pet
+/// revived if it dies of neglect.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Policy {
+    pub insurer: ActorId,
+    /// Last block covered by the premiums paid; the policy lapses after it.
+    pub paid_until: u64,
+    pub revivals_left: u32,
+}
+
+impl Policy {
+    pub fn covers(&self, block: u64) -> bool {
+        block <= self.paid_until && self.revivals_left > 0
+    }
+}
+
+/// Premium for the next period of a pet neglected for `neglected_blocks`.
+pub fn premium(neglected_blocks: u64) -> u128 {
+    let surcharge = (neglected_blocks / SURCHARGE_NEGLECT_BLOCKS) as u128 * NEGLECT_SURCHARGE;
+    (BASE_PREMIUM + surcharge).min(MAX_PREMIUM)
+}
@@ -1,79 +1,409 @@
 #![no_std]
 use ft_main_io::{FTokenAction, FTokenEvent, LogicAction};
-use gmeta::{In, InOut, Metadata};
-use gstd::{debug, exec, msg, prelude::*, ActorId, Debug, Decode, Encode, ReservationId, TypeInfo};
+use gmeta::{In, InOut, Metadata, Out};
+use gstd::{
+    debug, exec, msg, prelude::*, ActorId, CodeId, Debug, Decode, Encode, MessageId, ReservationId,
+    TypeInfo,
+};
+use seasons_io::SeasonalEvent;
 use store_io::{AttributeId, TransactionId};
 
+pub mod budget;
+pub mod care;
+pub mod commitment;
+pub mod decay;
+pub mod games;
+pub mod history;
+pub mod household;
+pub mod insurance;
+pub mod loan;
+pub mod migration;
+pub mod profile;
+pub mod shares;
+pub mod stats;
+pub mod wellbeing;
+
+pub use budget::{Budget, SpendLimit};
+pub use care::{CareHistory, CareRule, OverfeedRule};
+pub use commitment::{commitment_hash, Commitment, COMMIT_STAKE, REVEAL_BLOCKS};
+pub use decay::{DecayRemainder, Mood, DECAY_UNIT};
+pub use games::{Game, GameResult, Hand, Outcome};
+pub use history::{PetChange, PetEvent, Snapshot};
+pub use household::{Household, Proposal, ProposalId};
+pub use insurance::Policy;
+pub use loan::{Loan, LoanTerms, MAX_LOAN_BLOCKS};
+pub use migration::{StateEnvelope, STATE_VERSION};
+pub use profile::{PetMetadata, Profile, RenameFee, Stage};
+pub use shares::{Buyout, Shares};
+pub use stats::{LifetimeStats, TamagotchiStats};
+pub use wellbeing::Wellbeing;
+
 pub struct TamagotchiMetadata;
 
 impl Metadata for TamagotchiMetadata {
-    type Init = In<String>;
+    type Init = In<TmInit>;
     type Handle = InOut<TmAction, TmEvent>;
     type Signal = ();
     type Reply = ();
-    type Others = ();
+    type Others = Out<PetEvent>;
     type State = TamagotchiState;
 }
 
 pub const HUNGER_PER_BLOCK: u64 = 1;
 pub const ENERGY_PER_BLOCK: u64 = 2;
 pub const BOREDOM_PER_BLOCK: u64 = 2;
-pub const FILL_PER_SLEEP: u64 = 1000;
 pub const FILL_PER_FEED: u64 = 1000;
 pub const FILL_PER_ENTERTAINMENT: u64 = 1000;
 pub const MAX_MOOD_VALUE: u64 = 10000;
 pub const MIN_MOOD_VALUE: u64 = 1;
 
+pub const REST_PER_SLEEP_BLOCK: u64 = 20;
+/// Hunger grows this many times slower while the pet sleeps.
+pub const SLEEP_HUNGER_DIVISOR: u64 = 2;
+pub const MAX_SLEEP_BLOCKS: u32 = 500;
+pub const WAKE_UP_PENALTY: u64 = 500;
+
 pub const MOOD_LIMIT: u64 = 1000;
 pub const CHECK_INTERVAL: u32 = 60;
 
+pub const VISIT_FILL: u64 = 500;
+pub const FRIENDSHIP_PER_VISIT: u64 = 1;
+/// Blocks an accepted visit stays open for.
+pub const VISIT_ACCEPT_BLOCKS: u64 = 600;
+/// Blocks a visitor waits for the host to reply before giving up.
+pub const VISIT_TIMEOUT: u32 = 10;
+
 #[derive(Default, Encode, Decode, TypeInfo, Debug)]
 pub struct TamagotchiState {
     pub name: String,
     pub date_of_birth: u64,
-    pub owner: ActorId,
+    pub birth_block: u64,
+    pub household: Household,
     pub fed: u64,
     pub fed_block: u64,
     pub entertained: u64,
     pub entertained_block: u64,
     pub rested: u64,
     pub rested_block: u64,
+    /// Block the current or last nap ends at, cleared once the pet is woken up.
+    pub sleeping_until: Option<u64>,
+    pub feed_history: CareHistory,
+    pub play_history: CareHistory,
     pub allowed_account: Option<ActorId>,
     pub ft_contract: Option<ActorId>,
     pub transaction_id: u64,
     pub approve_transaction: Option<(TransactionId, ActorId, u128)>,
     pub reservations: Vec<ReservationId>,
+    pub lifecycle: Lifecycle,
+    pub predecessor: Option<ActorId>,
+    pub owed: BTreeMap<ActorId, u128>,
+    pub allowed_stores: BTreeMap<ActorId, Option<Budget>>,
+    pub delegate_budgets: BTreeMap<ActorId, Budget>,
+    pub allowances: BTreeMap<ActorId, Allowance>,
+    pub stats: LifetimeStats,
+    pub profile: Profile,
+    pub rename_fee: Option<RenameFee>,
+    pub attributes: BTreeSet<AttributeId>,
+    pub equipped: BTreeSet<AttributeId>,
+    pub wellbeing: Wellbeing,
+    pub last_checked_health: u64,
+    /// Friendship score with every pet this one has visited or hosted.
+    pub friends: BTreeMap<ActorId, u64>,
+    /// Pets allowed to visit, with the block until which they may come.
+    pub accepted_visits: BTreeMap<ActorId, u64>,
+    pub proposals: BTreeMap<ProposalId, Proposal>,
+    pub next_proposal_id: ProposalId,
+    /// Account allowed to pause the pet, set at init.
+    pub guardian: Option<ActorId>,
+    pub pause: Option<Pause>,
+    pub season_registry: Option<ActorId>,
+    /// Seasonal events from the registry that haven't ended yet.
+    pub seasonal_events: Vec<SeasonalEvent>,
+    /// Blocks between two state checks.
+    pub check_interval: u32,
+    /// The one `CheckState` message the loop is waiting for, if running.
+    pub scheduled_check: Option<ScheduledCheck>,
+    /// Hidden actions waiting to be revealed, one per account.
+    pub commitments: BTreeMap<ActorId, Commitment>,
+    pub insurance: Option<Policy>,
+    /// Standing offers from lenders, one per lender.
+    pub loan_offers: BTreeMap<ActorId, LoanTerms>,
+    pub loan: Option<Loan>,
+    pub shares: Option<Shares>,
+    pub decay_remainder: DecayRemainder,
+    /// Set while a premium is waiting for the token contract.
+    pub premium_pending: bool,
+    /// Token transfer the token contract hasn't answered yet, as
+    /// `(transaction_id, sender, recipient, amount)`.
+    pub transfer_transaction: Option<(TransactionId, ActorId, ActorId, u128)>,
+    /// FT the pet holds for accounts whose payouts failed, until they claim it.
+    pub unclaimed_tokens: BTreeMap<ActorId, u128>,
+}
+
+/// Last FT allowance the pet granted to a spender and who asked for it.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
+pub struct Allowance {
+    pub amount: u128,
+    pub approved_by: ActorId,
+}
+
+#[derive(Default, Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Lifecycle {
+    #[default]
+    Active,
+    Migrating,
+    Retired {
+        successor: ActorId,
+    },
+    /// Died of neglect at block `at` with no policy to revive it.
+    Dead {
+        at: u64,
+    },
+}
+
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ScheduledCheck {
+    pub message_id: MessageId,
+    pub due_block: u64,
+}
+
+/// Emergency stop set by the guardian.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Pause {
+    pub since: u64,
+    /// Whether feeding, playing, sleeping and visits stay allowed.
+    pub care_open: bool,
+}
+
+/// Schedules the `LoanDue` check for the block after `due_block`, the last
+/// one the loan can be repaid in.
+fn schedule_loan_due(due_block: u64, current_block_height: u64) -> MessageId {
+    let delay = (due_block + 1).saturating_sub(current_block_height).max(1);
+    msg::send_delayed(exec::program_id(), TmAction::LoanDue, 0, delay as u32)
+        .expect("Failed to schedule the loan check")
 }
 
 impl TamagotchiState {
     pub fn update_mood(&mut self, current_block_height: u64) {
-        self.fed = self
-            .fed
-            .saturating_sub(HUNGER_PER_BLOCK * (current_block_height - self.fed_block));
-        self.fed_block = current_block_height;
-        self.entertained = self
-            .entertained
-            .saturating_sub(BOREDOM_PER_BLOCK * (current_block_height - self.entertained_block));
-        self.entertained_block = current_block_height;
-        self.rested = self
-            .rested
-            .saturating_sub(ENERGY_PER_BLOCK * (current_block_height - self.rested_block));
-        self.rested_block = current_block_height;
+        let spans = |mood: Mood, from: u64| {
+            mood.spans(
+                &self.seasonal_events,
+                from,
+                current_block_height,
+                self.sleeping_until,
+            )
+        };
+        let hunger = spans(Mood::Hunger, self.fed_block);
+        let boredom = spans(Mood::Boredom, self.entertained_block);
+        let energy = spans(Mood::Energy, self.rested_block);
+        let remainder = &mut self.decay_remainder;
+        let healthy = [
+            decay::blocks_above_limit(Mood::Hunger, self.fed, remainder.hunger, &hunger),
+            decay::blocks_above_limit(Mood::Boredom, self.entertained, remainder.boredom, &boredom),
+            decay::blocks_above_limit(Mood::Energy, self.rested, remainder.energy, &energy),
+        ]
+        .into_iter()
+        .min()
+        .unwrap_or_default();
+        // A block behind the last update, as on a defaulted or imported
+        // state, counts as no time passing rather than underflowing
+        self.stats
+            .record_blocks(current_block_height.saturating_sub(self.fed_block), healthy);
+        let rested_asleep: u64 = energy
+            .iter()
+            .filter(|span| span.asleep)
+            .map(|span| span.blocks)
+            .sum();
+        let hunger = decay::points(&hunger, &mut remainder.hunger);
+        let boredom = decay::points(&boredom, &mut remainder.boredom);
+        let energy = decay::points(&energy, &mut remainder.energy);
+        self.fed = self.fed.saturating_sub(hunger);
+        self.fed_block = self.fed_block.max(current_block_height);
+        self.entertained = self.entertained.saturating_sub(boredom);
+        self.entertained_block = self.entertained_block.max(current_block_height);
+        self.rested = (self.rested + REST_PER_SLEEP_BLOCK * rested_asleep)
+            .min(MAX_MOOD_VALUE)
+            .saturating_sub(energy);
+        self.rested_block = self.rested_block.max(current_block_height);
+        self.seasonal_events
+            .retain(|event| event.end_block > current_block_height);
+        self.refresh_wellbeing();
     }
 
-    pub fn feed(&mut self) {
-        self.fed += FILL_PER_FEED;
+    pub fn refresh_wellbeing(&mut self) {
+        self.wellbeing = Wellbeing::new(
+            (self.fed, self.entertained, self.rested),
+            self.stats.current_neglect,
+            self.equipped.len(),
+        );
+    }
+
+    pub fn equip(&mut self, attribute_id: AttributeId) -> Result<(), TmError> {
+        if !self.attributes.contains(&attribute_id) {
+            return Err(TmError::AttributeNotOwned);
+        }
+        self.equipped.insert(attribute_id);
+        self.refresh_wellbeing();
+        Ok(())
+    }
+
+    pub fn unequip(&mut self, attribute_id: AttributeId) {
+        self.equipped.remove(&attribute_id);
+        self.refresh_wellbeing();
+    }
+
+    pub fn is_asleep(&self, current_block_height: u64) -> bool {
+        matches!(self.sleeping_until, Some(until) if until > current_block_height)
+    }
+
+    /// Cuts a nap short, costing the pet `WAKE_UP_PENALTY` rest.
+    fn wake_up_early(&mut self, current_block_height: u64) {
+        if self.is_asleep(current_block_height) {
+            self.sleeping_until = None;
+            self.rested = self.rested.saturating_sub(WAKE_UP_PENALTY);
+            TamagotchiState::verify_limit(&mut self.rested);
+        }
+    }
+
+    /// Ends the nap finishing at `until`, returning `false` if the pet was
+    /// already woken up or went to sleep again since.
+    pub fn wake_up(&mut self, until: u64) -> bool {
+        if self.sleeping_until != Some(until) {
+            return false;
+        }
+        self.sleeping_until = None;
+        true
+    }
+
+    pub fn feed(&mut self, current_block_height: u64) -> Result<(), TmError> {
+        let fill = self
+            .feed_history
+            .record(&care::FEED_RULE, current_block_height)?;
+        self.wake_up_early(current_block_height);
+        self.fed += fill;
+        self.stats.feeds += 1;
+        if self.fed > care::OVERFEED_RULE.threshold {
+            self.entertained = self.entertained.saturating_sub(care::OVERFEED_RULE.penalty);
+            TamagotchiState::verify_limit(&mut self.entertained);
+        }
         TamagotchiState::verify_limit(&mut self.fed);
+        self.refresh_wellbeing();
+        Ok(())
     }
 
-    pub fn play(&mut self) {
-        self.entertained += FILL_PER_ENTERTAINMENT;
+    /// Plays `game`, with the pet's move drawn from `random`.
+    pub fn play(
+        &mut self,
+        game: Game,
+        random: [u8; 32],
+        current_block_height: u64,
+    ) -> Result<GameResult, TmError> {
+        game.validate()?;
+        let fill = self
+            .play_history
+            .record(&care::PLAY_RULE, current_block_height)?;
+        let (pet, outcome) = game.play(random);
+        let reward = outcome.reward();
+        let xp = reward.xp + self.seasonal_xp(current_block_height);
+        let entertainment = fill * reward.fill_percent / 100;
+        self.wake_up_early(current_block_height);
+        self.entertained += entertainment;
+        self.stats.plays += 1;
+        self.stats.xp += xp;
         TamagotchiState::verify_limit(&mut self.entertained);
+        self.refresh_wellbeing();
+        Ok(GameResult {
+            player: game,
+            pet,
+            outcome,
+            entertainment,
+            xp,
+        })
     }
 
-    pub fn sleep(&mut self) {
-        self.rested += FILL_PER_SLEEP;
-        TamagotchiState::verify_limit(&mut self.rested);
+    /// Bonus XP per game for equipped attributes favoured by the events
+    /// running at `current_block_height`.
+    fn seasonal_xp(&self, current_block_height: u64) -> u64 {
+        self.seasonal_events
+            .iter()
+            .filter(|event| event.is_active(current_block_height))
+            .map(|event| {
+                let bonus_attributes = self
+                    .equipped
+                    .iter()
+                    .filter(|attribute| event.modifiers.bonus_attributes.contains(attribute))
+                    .count() as u64;
+                bonus_attributes * event.modifiers.attribute_xp
+            })
+            .sum()
+    }
+
+    pub fn accept_visit(&mut self, other_pet: ActorId, current_block_height: u64) {
+        self.accepted_visits
+            .insert(other_pet, current_block_height + VISIT_ACCEPT_BLOCKS);
+    }
+
+    /// Hosts `visitor` if this pet's owner accepted the visit in time.
+    pub fn host_visit(
+        &mut self,
+        visitor: ActorId,
+        current_block_height: u64,
+    ) -> Result<u64, TmError> {
+        match self.accepted_visits.remove(&visitor) {
+            Some(until) if until >= current_block_height => Ok(self.enjoy_visit(visitor)),
+            _ => Err(TmError::VisitNotAccepted),
+        }
+    }
+
+    /// Entertains the pet after time spent with `friend` and returns their
+    /// new friendship score.
+    pub fn enjoy_visit(&mut self, friend: ActorId) -> u64 {
+        self.entertained += VISIT_FILL;
+        TamagotchiState::verify_limit(&mut self.entertained);
+        self.refresh_wellbeing();
+        let friendship = self.friends.entry(friend).or_default();
+        *friendship += FRIENDSHIP_PER_VISIT;
+        *friendship
+    }
+
+    /// Puts the pet to bed for `blocks`, returning the block it wakes up at.
+    pub fn sleep(&mut self, blocks: u32, current_block_height: u64) -> Result<u64, TmError> {
+        if self.is_asleep(current_block_height) {
+            return Err(TmError::AlreadyAsleep);
+        }
+        if blocks == 0 || blocks > MAX_SLEEP_BLOCKS {
+            return Err(TmError::InvalidSleepDuration);
+        }
+        let until = current_block_height + blocks as u64;
+        self.sleeping_until = Some(until);
+        self.stats.sleeps += 1;
+        Ok(until)
+    }
+
+    pub fn lifetime_stats(
+        &self,
+        current_block_height: u64,
+        current_timestamp: u64,
+    ) -> TamagotchiStats {
+        TamagotchiStats {
+            age_blocks: current_block_height.saturating_sub(self.birth_block),
+            age_ms: current_timestamp.saturating_sub(self.date_of_birth),
+            feeds: self.stats.feeds,
+            plays: self.stats.plays,
+            sleeps: self.stats.sleeps,
+            xp: self.stats.xp,
+            neglected_blocks: self.stats.neglected_blocks,
+            longest_streak: self.stats.longest_streak(),
+            ft_spent: self.stats.ft_spent,
+        }
+    }
+
+    pub fn metadata(&self, current_block_height: u64) -> PetMetadata {
+        PetMetadata {
+            name: self.name.clone(),
+            profile: self.profile.clone(),
+            stage: Stage::from_age(current_block_height.saturating_sub(self.birth_block)),
+        }
     }
 
     pub fn verify_limit(mood_param: &mut u64) {
@@ -85,8 +415,130 @@ impl TamagotchiState {
         }
     }
 
-    pub fn verify_ownership(&self, source: ActorId) -> bool {
-        self.owner == source
+    pub fn is_active(&self) -> bool {
+        self.lifecycle == Lifecycle::Active
+    }
+
+    pub fn is_dead(&self) -> bool {
+        matches!(self.lifecycle, Lifecycle::Dead { .. })
+    }
+
+    /// Kills the pet once its neglect has lasted `DEATH_NEGLECT_BLOCKS`,
+    /// unless a policy that was active at the time revives it.
+    pub fn check_survival(&mut self, current_block_height: u64) {
+        let neglect = self.stats.current_neglect;
+        if !self.is_active() || neglect < insurance::DEATH_NEGLECT_BLOCKS {
+            return;
+        }
+        let died_at = current_block_height - (neglect - insurance::DEATH_NEGLECT_BLOCKS);
+        let event = match &mut self.insurance {
+            Some(policy) if policy.covers(died_at) => {
+                policy.revivals_left -= 1;
+                let revivals_left = policy.revivals_left;
+                self.revive(current_block_height);
+                TmEvent::Revived { revivals_left }
+            }
+            _ => {
+                self.lifecycle = Lifecycle::Dead { at: died_at };
+                TmEvent::Died
+            }
+        };
+        msg::send(self.household.contact(), event, 0).expect("Failed to send survival event");
+    }
+
+    fn revive(&mut self, current_block_height: u64) {
+        self.fed = insurance::REVIVAL_MOOD;
+        self.fed_block = current_block_height;
+        self.entertained = insurance::REVIVAL_MOOD;
+        self.entertained_block = current_block_height;
+        self.rested = insurance::REVIVAL_MOOD;
+        self.rested_block = current_block_height;
+        self.sleeping_until = None;
+        self.stats.current_neglect = 0;
+        self.refresh_wellbeing();
+    }
+
+    /// Drops the policy once its premiums stop covering the current block.
+    pub fn lapse_insurance(&mut self, current_block_height: u64) {
+        // A premium in flight may still extend it
+        if self.premium_pending {
+            return;
+        }
+        if matches!(self.insurance, Some(policy) if policy.paid_until < current_block_height) {
+            self.insurance = None;
+        }
+    }
+
+    /// Premium for the next period, priced by how long the pet has been neglected.
+    pub fn premium(&self) -> u128 {
+        insurance::premium(self.stats.neglected_blocks)
+    }
+
+    /// Opens a policy with `insurer`, paying the first premium as `pay` does.
+    pub async fn insure(
+        &mut self,
+        payer: ActorId,
+        insurer: ActorId,
+        current_block_height: u64,
+        native: bool,
+    ) -> Result<TmEvent, TmError> {
+        if self.insurance.is_some() {
+            return Err(TmError::AlreadyInsured);
+        }
+        self.ensure_no_premium_pending()?;
+        self.pay_premium_to(payer, insurer, native).await?;
+        let paid_until = current_block_height + insurance::PREMIUM_PERIOD;
+        self.insurance = Some(Policy {
+            insurer,
+            paid_until,
+            revivals_left: insurance::POLICY_REVIVALS,
+        });
+        Ok(TmEvent::Insured { paid_until })
+    }
+
+    /// Extends the policy by a period; at most one period can be paid ahead.
+    pub async fn pay_premium(
+        &mut self,
+        payer: ActorId,
+        current_block_height: u64,
+        native: bool,
+    ) -> Result<TmEvent, TmError> {
+        self.ensure_no_premium_pending()?;
+        let policy = self.insurance.ok_or(TmError::NotInsured)?;
+        if policy.paid_until >= current_block_height + insurance::PREMIUM_PERIOD {
+            return Err(TmError::PremiumNotDue);
+        }
+        self.pay_premium_to(payer, policy.insurer, native).await?;
+        let paid_until = policy.paid_until + insurance::PREMIUM_PERIOD;
+        if let Some(policy) = &mut self.insurance {
+            policy.paid_until = paid_until;
+        }
+        Ok(TmEvent::PremiumPaid { paid_until })
+    }
+
+    fn ensure_no_premium_pending(&self) -> Result<(), TmError> {
+        if self.premium_pending {
+            return Err(TmError::PremiumPending);
+        }
+        Ok(())
+    }
+
+    /// Pays the premium to `insurer`, holding off other premiums and the
+    /// policy lapsing until the token contract replies.
+    async fn pay_premium_to(
+        &mut self,
+        payer: ActorId,
+        insurer: ActorId,
+        native: bool,
+    ) -> Result<(), TmError> {
+        self.premium_pending = true;
+        let result = self.pay(payer, insurer, self.premium(), native).await;
+        self.premium_pending = false;
+        result
+    }
+
+    pub fn verify_membership(&self, source: ActorId) -> bool {
+        self.household.is_member(source)
     }
 
     pub fn verify_allowed_account(&self, source: ActorId) -> bool {
@@ -94,10 +546,359 @@ impl TamagotchiState {
     }
 
     pub fn verify_permission(&self, source: ActorId) -> bool {
-        self.verify_ownership(source) || self.verify_allowed_account(source)
+        self.verify_membership(source) || self.verify_allowed_account(source)
+    }
+
+    pub fn ensure_membership(&self, source: ActorId) -> Result<(), TmError> {
+        if !self.verify_membership(source) {
+            return Err(TmError::NotMember);
+        }
+        Ok(())
+    }
+
+    pub fn ensure_permission(&self, source: ActorId) -> Result<(), TmError> {
+        if !self.verify_permission(source) {
+            return Err(TmError::NotPermitted);
+        }
+        Ok(())
+    }
+
+    /// Guarded actions can only be done directly by a member, and only while
+    /// the household doesn't need proposals.
+    pub fn ensure_direct(&self, source: ActorId) -> Result<(), TmError> {
+        if self.household.needs_proposals() {
+            return Err(TmError::ProposalRequired);
+        }
+        self.ensure_membership(source)
+    }
+
+    pub fn ensure_guardian(&self, source: ActorId) -> Result<(), TmError> {
+        if self.guardian != Some(source) {
+            return Err(TmError::NotGuardian);
+        }
+        Ok(())
+    }
+
+    /// Rejects `action` if the pet is paused and it moves tokens or
+    /// ownership, or is care while care is closed.
+    pub fn ensure_unpaused(&self, action: &TmAction, value: u128) -> Result<(), TmError> {
+        match self.pause {
+            Some(pause)
+                if value > 0 || action.moves_assets() || (action.is_care() && !pause.care_open) =>
+            {
+                Err(TmError::Paused)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn set_household(&mut self, household: Household) {
+        self.household = household;
+        // Approvals given by former members no longer count
+        self.proposals.clear();
+    }
+
+    pub fn propose(
+        &mut self,
+        source: ActorId,
+        action: TmAction,
+        expires_at: u64,
+        current_block_height: u64,
+    ) -> Result<ProposalId, TmError> {
+        self.ensure_membership(source)?;
+        if !household::is_guarded(&action) {
+            return Err(TmError::NotProposable);
+        }
+        if let TmAction::SetHousehold(household) = &action {
+            household.validate()?;
+        }
+        if let TmAction::Fractionalise(holders) = &action {
+            Shares::new(holders.clone())?;
+        }
+        if expires_at <= current_block_height {
+            return Err(TmError::ProposalExpired);
+        }
+        self.proposals
+            .retain(|_, proposal| proposal.expires_at > current_block_height);
+        let id = self.next_proposal_id;
+        self.next_proposal_id += 1;
+        self.proposals.insert(
+            id,
+            Proposal {
+                action,
+                approvals: BTreeSet::new(),
+                expires_at,
+            },
+        );
+        Ok(id)
+    }
+
+    /// Adds the approval of `source`, returning the action once enough
+    /// members have agreed to it.
+    pub fn approve_proposal(
+        &mut self,
+        source: ActorId,
+        id: ProposalId,
+        current_block_height: u64,
+    ) -> Result<Option<TmAction>, TmError> {
+        self.ensure_membership(source)?;
+        let proposal = self
+            .proposals
+            .get_mut(&id)
+            .ok_or(TmError::ProposalNotFound)?;
+        if proposal.expires_at <= current_block_height {
+            self.proposals.remove(&id);
+            return Err(TmError::ProposalExpired);
+        }
+        proposal.approvals.insert(source);
+        if proposal.approvals.len() < self.household.threshold as usize {
+            return Ok(None);
+        }
+        Ok(self.proposals.remove(&id).map(|proposal| proposal.action))
+    }
+
+    /// Credits native value the contract now holds on behalf of `account`.
+    pub fn deposit(&mut self, account: ActorId, value: u128) {
+        if value > 0 {
+            *self.owed.entry(account).or_default() += value;
+        }
+    }
+
+    /// Pays `price` to `recipient` out of the native value credited to `payer`.
+    ///
+    /// The value attached to a message is deposited before the action runs,
+    /// so whatever exceeds the price stays with the payer until withdrawn.
+    pub fn charge_native(
+        &mut self,
+        payer: ActorId,
+        recipient: ActorId,
+        price: u128,
+    ) -> Result<(), TmError> {
+        if !self.take_owed(payer, price) {
+            return Err(TmError::InsufficientValue);
+        }
+        self.deposit(recipient, price);
+        Ok(())
+    }
+
+    /// Pays `amount` to `recipient`, in native value credited to `payer` if
+    /// `native`, or else out of `payer`'s FT through its allowance to the pet.
+    async fn pay(
+        &mut self,
+        payer: ActorId,
+        recipient: ActorId,
+        amount: u128,
+        native: bool,
+    ) -> Result<(), TmError> {
+        if native {
+            return self.charge_native(payer, recipient, amount);
+        }
+        self.move_tokens(payer, recipient, amount).await
     }
 
-    pub async fn approve_tokens(&mut self, account: &ActorId, amount: u128) -> TmEvent {
+    /// Takes `amount` out of the native value credited to `account`, or
+    /// returns `false` if there isn't that much.
+    fn take_owed(&mut self, account: ActorId, amount: u128) -> bool {
+        let balance = self.owed.get(&account).copied().unwrap_or_default();
+        if balance < amount {
+            return false;
+        }
+        if balance == amount {
+            self.owed.remove(&account);
+        } else {
+            self.owed.insert(account, balance - amount);
+        }
+        true
+    }
+
+    /// Holds `COMMIT_STAKE` of `source`'s native value against `hash` and
+    /// returns the block it must be revealed by.
+    pub fn commit(
+        &mut self,
+        source: ActorId,
+        hash: [u8; 32],
+        current_block_height: u64,
+    ) -> Result<u64, TmError> {
+        if self.commitments.contains_key(&source) {
+            return Err(TmError::CommitmentPending);
+        }
+        if !self.take_owed(source, COMMIT_STAKE) {
+            return Err(TmError::StakeRequired);
+        }
+        let reveal_by = current_block_height + REVEAL_BLOCKS;
+        self.commitments.insert(
+            source,
+            Commitment {
+                hash,
+                reveal_by,
+                stake: COMMIT_STAKE,
+            },
+        );
+        Ok(reveal_by)
+    }
+
+    /// Settles `source`'s commitment if `payload` and `salt` match it,
+    /// crediting the stake back. Expired commitments are already gone by then.
+    pub fn reveal(
+        &mut self,
+        source: ActorId,
+        payload: &TmAction,
+        salt: &[u8; 32],
+    ) -> Result<(), TmError> {
+        match self.commitments.get(&source) {
+            None => return Err(TmError::CommitmentNotFound),
+            Some(commitment) if commitment.hash != commitment_hash(&source, payload, salt) => {
+                return Err(TmError::RevealMismatch)
+            }
+            Some(_) => {}
+        }
+        if let Some(commitment) = self.commitments.remove(&source) {
+            self.deposit(source, commitment.stake);
+        }
+        Ok(())
+    }
+
+    /// Drops commitments past their deadline, forfeiting their stakes to the
+    /// household.
+    pub fn expire_commitments(&mut self, current_block_height: u64) {
+        let mut forfeited = 0;
+        self.commitments.retain(|_, commitment| {
+            let open = commitment.reveal_by >= current_block_height;
+            if !open {
+                forfeited += commitment.stake;
+            }
+            open
+        });
+        self.deposit(self.household.contact(), forfeited);
+    }
+
+    pub fn withdraw(&mut self, account: ActorId) -> u128 {
+        self.owed.remove(&account).unwrap_or_default()
+    }
+
+    pub fn verify_store(&self, store_id: ActorId) -> bool {
+        self.allowed_stores.contains_key(&store_id)
+    }
+
+    pub fn allow_store(
+        &mut self,
+        store_id: ActorId,
+        limit: Option<SpendLimit>,
+        current_block_height: u64,
+    ) {
+        let budget = limit.map(|limit| Budget::new(limit, current_block_height));
+        self.allowed_stores.insert(store_id, budget);
+    }
+
+    /// Allowed stores with the amount each can still be approved this period,
+    /// `None` meaning the store has no cap.
+    pub fn store_budgets(&self, current_block_height: u64) -> Vec<(ActorId, Option<u128>)> {
+        self.allowed_stores
+            .iter()
+            .map(|(store_id, budget)| {
+                (
+                    *store_id,
+                    budget
+                        .as_ref()
+                        .map(|budget| budget.remaining(current_block_height)),
+                )
+            })
+            .collect()
+    }
+
+    fn store_budget(&mut self, account: &ActorId) -> Option<&mut Budget> {
+        self.allowed_stores
+            .get_mut(account)
+            .and_then(Option::as_mut)
+    }
+
+    pub fn set_delegate_budget(
+        &mut self,
+        delegate: ActorId,
+        limit: Option<SpendLimit>,
+        current_block_height: u64,
+    ) {
+        match limit {
+            Some(limit) => {
+                self.delegate_budgets
+                    .insert(delegate, Budget::new(limit, current_block_height));
+            }
+            None => {
+                self.delegate_budgets.remove(&delegate);
+            }
+        }
+    }
+
+    /// Budget of `source` when it acts as a delegate; members are never capped.
+    fn delegate_budget(&mut self, source: &ActorId) -> Option<&mut Budget> {
+        if self.verify_membership(*source) {
+            return None;
+        }
+        self.delegate_budgets.get_mut(source)
+    }
+
+    /// Charges a delegate for a purchase that draws on an allowance someone
+    /// else approved, returning the amount charged.
+    ///
+    /// Only members approve allowances, so an account buying on one it
+    /// approved itself isn't charged again. With `native` the delegate pays
+    /// in native value credited to it instead of out of its budget; the
+    /// household gets it once the store confirms the sale.
+    pub fn charge_purchase(
+        &mut self,
+        source: ActorId,
+        store_id: ActorId,
+        current_block_height: u64,
+        native: bool,
+    ) -> Result<u128, TmError> {
+        let amount = match self.allowances.get(&store_id) {
+            Some(allowance) if allowance.approved_by != source => allowance.amount,
+            _ => return Ok(0),
+        };
+        if native {
+            if !self.take_owed(source, amount) {
+                return Err(TmError::InsufficientValue);
+            }
+            return Ok(amount);
+        }
+        if let Some(budget) = self.delegate_budget(&source) {
+            if !budget.spend(amount, current_block_height) {
+                return Err(TmError::BudgetExceeded {
+                    remaining: budget.remaining(current_block_height),
+                });
+            }
+        }
+        Ok(amount)
+    }
+
+    pub fn settle_purchase(
+        &mut self,
+        source: ActorId,
+        store_id: ActorId,
+        charged: u128,
+        success: bool,
+        native: bool,
+    ) {
+        if success {
+            if let Some(allowance) = self.allowances.get_mut(&store_id) {
+                allowance.approved_by = source;
+            }
+            if native {
+                self.deposit(self.household.contact(), charged);
+            }
+        } else if native {
+            self.deposit(source, charged);
+        } else if let Some(budget) = self.delegate_budget(&source) {
+            budget.refund(charged);
+        }
+    }
+
+    pub async fn approve_tokens(
+        &mut self,
+        source: ActorId,
+        account: &ActorId,
+        amount: u128,
+    ) -> TmEvent {
         let (transaction_id, account, amount) = if let Some((
             ft_transaction_id,
             prev_account,
@@ -110,6 +911,14 @@ impl TamagotchiState {
                 (ft_transaction_id, prev_account, prev_amount)
             }
         } else {
+            let current_block_height = exec::block_height() as u64;
+            if let Some(budget) = self.store_budget(account) {
+                if !budget.spend(amount, current_block_height) {
+                    return TmEvent::Error(TmError::StoreCapExceeded {
+                        remaining: budget.remaining(current_block_height),
+                    });
+                }
+            }
             let ft_transaction_id = self.transaction_id;
             self.transaction_id = self.transaction_id.wrapping_add(1);
             self.approve_transaction = Some((ft_transaction_id, *account, amount));
@@ -133,13 +942,442 @@ impl TamagotchiState {
             .await;
             self.approve_transaction = None;
             match result {
-                Ok(_) => return TmEvent::Approve(account),
-                Err(_) => return TmEvent::ApprovalError,
+                Ok(FTokenEvent::Ok) => {
+                    self.stats.ft_spent += amount;
+                    self.allowances.insert(
+                        account,
+                        Allowance {
+                            amount,
+                            approved_by: source,
+                        },
+                    );
+                    TmEvent::TokensApproved { account, amount }
+                }
+                _ => {
+                    if let Some(budget) = self.store_budget(&account) {
+                        budget.refund(amount);
+                    }
+                    TmEvent::ApprovalError
+                }
             }
         } else {
             debug!("FT contract not set");
             panic!("FT contract not set");
+        }
+    }
+
+    /// Moves `amount` from `sender` to `recipient`; any sender other than
+    /// the pet must have approved it as a spender on the token contract.
+    async fn move_tokens(
+        &mut self,
+        sender: ActorId,
+        recipient: ActorId,
+        amount: u128,
+    ) -> Result<(), TmError> {
+        let contract = self.ft_contract.ok_or(TmError::TokenContractNotSet)?;
+        // Retrying an unanswered transfer reuses its id, so the token
+        // contract moves the tokens at most once
+        let transaction_id = match self.transfer_transaction {
+            Some((transaction_id, pending_sender, pending_recipient, pending_amount))
+                if (pending_sender, pending_recipient, pending_amount)
+                    == (sender, recipient, amount) =>
+            {
+                transaction_id
+            }
+            Some(_) => return Err(TmError::TransactionPending),
+            None => {
+                let transaction_id = self.transaction_id;
+                self.transaction_id = self.transaction_id.wrapping_add(1);
+                self.transfer_transaction = Some((transaction_id, sender, recipient, amount));
+                transaction_id
+            }
+        };
+        let result = msg::send_for_reply_as::<_, FTokenEvent>(
+            contract,
+            FTokenAction::Message {
+                transaction_id,
+                payload: LogicAction::Transfer {
+                    sender,
+                    recipient,
+                    amount,
+                },
+            },
+            0,
+            0,
+        )
+        .expect("Error sending transfer tokens message")
+        .await;
+        match result {
+            Ok(event) => {
+                self.transfer_transaction = None;
+                match event {
+                    FTokenEvent::Ok => Ok(()),
+                    _ => Err(TmError::TokenTransferFailed),
+                }
+            }
+            // Without an answer the tokens may have moved, so the transfer
+            // stays pending until it's retried
+            Err(_) => Err(TmError::TokenTransferFailed),
+        }
+    }
+
+    /// Rejects changes of ownership while the pet is collateral for a loan
+    /// or split into shares.
+    pub fn ensure_unencumbered(&self) -> Result<(), TmError> {
+        self.ensure_whole()?;
+        if self.loan.is_some() {
+            return Err(TmError::Collateralized);
+        }
+        Ok(())
+    }
+
+    /// Native value held for others, which goes along when the pet migrates:
+    /// what it owes and the stakes of commitments not yet revealed.
+    pub fn held_value(&self) -> u128 {
+        let stakes: u128 = self
+            .commitments
+            .values()
+            .map(|commitment| commitment.stake)
+            .sum();
+        self.owed.values().sum::<u128>() + stakes
+    }
+
+    /// Migrating hands the state over as it is, so it waits for a pending
+    /// token approval, transfer or premium, for shareholders to claim their
+    /// tokens and for the pet to be unencumbered.
+    pub fn ensure_migratable(&self) -> Result<(), TmError> {
+        if self.approve_transaction.is_some() || self.transfer_transaction.is_some() {
+            return Err(TmError::TransactionPending);
+        }
+        self.ensure_no_premium_pending()?;
+        // The tokens stay with this program's balance
+        if !self.unclaimed_tokens.is_empty() {
+            return Err(TmError::TokensUnclaimed);
+        }
+        self.ensure_unencumbered()
+    }
+
+    /// Rejects changes to where the pet's tokens can go while they're owed
+    /// to its shareholders.
+    pub fn ensure_whole(&self) -> Result<(), TmError> {
+        if self.shares.is_some() {
+            return Err(TmError::Fractionalised);
+        }
+        Ok(())
+    }
+
+    pub fn fractionalise(&mut self, holders: Vec<(ActorId, u64)>) -> Result<u64, TmError> {
+        self.ensure_unencumbered()?;
+        let shares = Shares::new(holders)?;
+        let total = shares.total;
+        self.set_household(shares.household());
+        self.shares = Some(shares);
+        Ok(total)
+    }
+
+    fn shares_mut(&mut self) -> Result<&mut Shares, TmError> {
+        self.shares.as_mut().ok_or(TmError::NotFractionalised)
+    }
+
+    pub fn transfer_shares(
+        &mut self,
+        from: ActorId,
+        to: ActorId,
+        amount: u64,
+    ) -> Result<(), TmError> {
+        let shares = self.shares_mut()?;
+        shares.transfer(from, to, amount)?;
+        // Whoever gathers every share owns the pet outright
+        let household = if shares.held_by(&to) == shares.total {
+            self.shares = None;
+            Household::solo(to)
+        } else {
+            shares.household()
+        };
+        if household != self.household {
+            self.set_household(household);
+        }
+        Ok(())
+    }
+
+    pub fn offer_buyout(&mut self, buyer: ActorId, price_per_share: u128) -> Result<(), TmError> {
+        let shares = self.shares_mut()?;
+        if shares.buyout.is_some() {
+            return Err(TmError::BuyoutPending);
+        }
+        shares.buyout = Some(Buyout {
+            buyer,
+            price_per_share,
+            votes: BTreeSet::new(),
+            settling: false,
+        });
+        Ok(())
+    }
+
+    pub fn cancel_buyout(&mut self, source: ActorId) -> Result<(), TmError> {
+        let shares = self.shares_mut()?;
+        shares.ensure_not_settling()?;
+        match &shares.buyout {
+            Some(buyout) if buyout.buyer == source => {
+                shares.buyout = None;
+                Ok(())
+            }
+            Some(_) => Err(TmError::NotBuyer),
+            None => Err(TmError::NoBuyout),
+        }
+    }
+
+    /// Records `holder`'s vote and settles the buyout once voters hold a
+    /// majority: the buyer pays for every share it doesn't hold, the holders
+    /// are paid out and the buyer becomes the sole owner. A failed payment
+    /// drops the offer.
+    pub async fn vote_buyout(&mut self, holder: ActorId) -> Result<TmEvent, TmError> {
+        let shares = self.shares_mut()?;
+        shares.ensure_not_settling()?;
+        if shares.held_by(&holder) == 0 {
+            return Err(TmError::NotShareholder);
+        }
+        shares
+            .buyout
+            .as_mut()
+            .ok_or(TmError::NoBuyout)?
+            .votes
+            .insert(holder);
+        if !shares.has_majority() {
+            return Ok(TmEvent::BuyoutVoted {
+                votes: shares.votes(),
+            });
+        }
+        let Some(buyout) = shares.buyout.as_mut() else {
+            unreachable!("The buyout was just voted for");
+        };
+        buyout.settling = true;
+        let (buyer, price_per_share) = (buyout.buyer, buyout.price_per_share);
+        let payouts: Vec<(ActorId, u128)> = shares
+            .holders
+            .iter()
+            .filter(|(account, _)| **account != buyer)
+            .map(|(account, held)| (*account, price_per_share.saturating_mul(*held as u128)))
+            .collect();
+        let price = payouts
+            .iter()
+            .fold(0u128, |price, (_, payout)| price.saturating_add(*payout));
+        if let Err(error) = self.move_tokens(buyer, exec::program_id(), price).await {
+            if let Some(shares) = &mut self.shares {
+                shares.buyout = None;
+            }
+            return Err(error);
+        }
+        let unpaid = self.pay_out(payouts).await;
+        self.shares = None;
+        self.set_household(Household::solo(buyer));
+        Ok(TmEvent::BoughtOut { buyer, unpaid })
+    }
+
+    /// Pulls `amount` from `payer`'s FT, through its allowance to the pet,
+    /// and pays it out to the shareholders pro rata. Only revenue paid in
+    /// this way is shared; the pet's own balance stays with the pet.
+    pub async fn distribute_revenue(
+        &mut self,
+        payer: ActorId,
+        amount: u128,
+    ) -> Result<TmEvent, TmError> {
+        let shares = self.shares_mut()?;
+        shares.ensure_not_settling()?;
+        let payouts = shares.payouts(amount);
+        // The payouts are worked out up front, so the holders can't change
+        // until they're sent
+        shares.distributing = true;
+        let unpaid = match self.move_tokens(payer, exec::program_id(), amount).await {
+            Ok(()) => Ok(self.pay_out(payouts).await),
+            Err(error) => Err(error),
+        };
+        if let Some(shares) = &mut self.shares {
+            shares.distributing = false;
+        }
+        Ok(TmEvent::RevenueDistributed {
+            amount,
+            unpaid: unpaid?,
+        })
+    }
+
+    /// Sends each payout from the pet's FT balance and returns the accounts
+    /// that couldn't be paid, whose cut they can claim later.
+    async fn pay_out(&mut self, payouts: Vec<(ActorId, u128)>) -> Vec<ActorId> {
+        let mut unpaid = Vec::new();
+        for (account, amount) in payouts {
+            if self
+                .move_tokens(exec::program_id(), account, amount)
+                .await
+                .is_err()
+            {
+                self.credit_unclaimed(account, amount);
+                unpaid.push(account);
+            }
+        }
+        unpaid
+    }
+
+    /// Credits a payout that failed, unless the token contract never
+    /// answered it: it may have gone through, and claiming retries it.
+    fn credit_unclaimed(&mut self, account: ActorId, amount: u128) {
+        let unanswered = matches!(
+            self.transfer_transaction,
+            Some((_, sender, recipient, pending))
+                if (sender, recipient, pending) == (exec::program_id(), account, amount)
+        );
+        if !unanswered {
+            *self.unclaimed_tokens.entry(account).or_default() += amount;
+        }
+    }
+
+    /// Sends `account` the tokens its failed payouts left with the pet,
+    /// retrying first a payout to it the token contract never answered.
+    pub async fn claim_tokens(&mut self, account: ActorId) -> Result<TmEvent, TmError> {
+        let program_id = exec::program_id();
+        let amount = match self.transfer_transaction {
+            Some((_, sender, recipient, amount))
+                if (sender, recipient) == (program_id, account) =>
+            {
+                amount
+            }
+            _ => self
+                .unclaimed_tokens
+                .remove(&account)
+                .ok_or(TmError::NothingToClaim)?,
+        };
+        if let Err(error) = self.move_tokens(program_id, account, amount).await {
+            self.credit_unclaimed(account, amount);
+            return Err(error);
+        }
+        Ok(TmEvent::TokensClaimed(amount))
+    }
+
+    pub fn offer_loan(&mut self, lender: ActorId, terms: LoanTerms) -> Result<(), TmError> {
+        terms.validate()?;
+        self.loan_offers.insert(lender, terms);
+        Ok(())
+    }
+
+    /// Takes `lender`'s offer, paying the principal from the lender's FT
+    /// balance to `borrower`, and locks the pet until the loan is repaid.
+    ///
+    /// The pet is locked before the tokens move so a second acceptance can't
+    /// race this one; if the transfer fails the lock and the offer are
+    /// restored.
+    pub async fn accept_loan(
+        &mut self,
+        borrower: ActorId,
+        lender: ActorId,
+        current_block_height: u64,
+    ) -> Result<TmEvent, TmError> {
+        self.ensure_unencumbered()?;
+        let terms = self
+            .loan_offers
+            .remove(&lender)
+            .ok_or(TmError::LoanOfferNotFound)?;
+        let due_block = current_block_height + terms.duration as u64;
+        let due_check = schedule_loan_due(due_block, current_block_height);
+        self.loan = Some(Loan {
+            lender,
+            terms,
+            due_block,
+            due_check,
+            repaying: false,
+        });
+        if let Err(error) = self.move_tokens(lender, borrower, terms.principal).await {
+            self.loan = None;
+            self.loan_offers.insert(lender, terms);
+            return Err(error);
+        }
+        Ok(TmEvent::LoanAccepted { lender, due_block })
+    }
+
+    /// Pays the lender back from `payer`'s FT balance, unlocking the pet.
+    pub async fn repay_loan(
+        &mut self,
+        payer: ActorId,
+        current_block_height: u64,
+    ) -> Result<TmEvent, TmError> {
+        let loan = self.loan.ok_or(TmError::NoLoan)?;
+        if loan.repaying {
+            return Err(TmError::RepaymentPending);
+        }
+        if current_block_height > loan.due_block {
+            return Err(TmError::LoanOverdue);
+        }
+        self.set_repaying(true);
+        match self
+            .move_tokens(payer, loan.lender, loan.terms.repayment)
+            .await
+        {
+            Ok(()) => {
+                self.loan = None;
+                Ok(TmEvent::LoanRepaid)
+            }
+            Err(error) => {
+                self.set_repaying(false);
+                Err(error)
+            }
+        }
+    }
+
+    fn set_repaying(&mut self, repaying: bool) {
+        if let Some(loan) = &mut self.loan {
+            loan.repaying = repaying;
+        }
+    }
+
+    /// Handles a `LoanDue` message, handing the pet to the lender if the
+    /// loan it was scheduled for is still unpaid.
+    pub fn settle_loan_due(&mut self, message_id: MessageId) {
+        let loan = match self.loan {
+            Some(loan) if loan.due_check == message_id => loan,
+            _ => return,
         };
+        if self.pause.is_some() {
+            // Unpausing schedules a new check past the extended deadline
+            return;
+        }
+        if loan.repaying {
+            // Look again once the repayment in flight has settled
+            let due_check = msg::send_delayed(exec::program_id(), TmAction::LoanDue, 0, 1)
+                .expect("Failed to schedule the loan check");
+            if let Some(loan) = &mut self.loan {
+                loan.due_check = due_check;
+            }
+            return;
+        }
+        let defaulted = TmEvent::LoanDefaulted {
+            lender: loan.lender,
+        };
+        msg::send(self.household.contact(), defaulted.clone(), 0)
+            .expect("Failed to send loan default");
+        msg::send(loan.lender, defaulted, 0).expect("Failed to send loan default");
+        self.loan = None;
+        self.set_household(Household::solo(loan.lender));
+    }
+
+    /// Lifts the guardian's pause. A loan can't be repaid while paused, so its
+    /// deadline moves back by the blocks the pause lasted.
+    pub fn unpause(&mut self, current_block_height: u64) {
+        let Some(pause) = self.pause.take() else {
+            return;
+        };
+        if let Some(loan) = &mut self.loan {
+            loan.due_block += current_block_height.saturating_sub(pause.since);
+            loan.due_check = schedule_loan_due(loan.due_block, current_block_height);
+        }
+    }
+
+    /// Renames the pet, with `renamer` paying the fee out of its own FT.
+    pub async fn rename(&mut self, renamer: ActorId, name: String) -> Result<TmEvent, TmError> {
+        profile::validate_name(&name)?;
+        if let Some(fee) = self.rename_fee {
+            self.move_tokens(renamer, fee.recipient, fee.amount).await?;
+        }
+        self.name = name.clone();
+        Ok(TmEvent::Renamed(name))
     }
 
     pub fn make_reservation(&mut self, amount: u64, duration: u32) -> TmEvent {
@@ -148,45 +1386,113 @@ impl TamagotchiState {
         TmEvent::GasReserved
     }
 
-    pub fn check_state_flow(&mut self) {
-        let events: [TmEvent; 3] = [TmEvent::FeedMe, TmEvent::PlayWithMe, TmEvent::WantToSleep];
-        for event in events.iter() {
-            if self.reservations.is_empty() {
-                msg::send(self.owner, TmEvent::MakeReservation, 0)
-                    .expect("Error sending make reservation message");
-                break;
-            }
-            let reservation_id = self.reservations.pop().unwrap();
-            if self.check_mood_in_limit(event) != TmEvent::SelfCheck {
-                self.send_check_feedback(reservation_id, event.clone());
+    /// Schedules the next state check, replacing any check already scheduled.
+    pub fn schedule_check(&mut self, current_block_height: u64) {
+        let message_id = msg::send_delayed(
+            exec::program_id(),
+            TmAction::CheckState,
+            0,
+            self.check_interval,
+        )
+        .expect("Error sending delayed message");
+        self.scheduled_check = Some(ScheduledCheck {
+            message_id,
+            due_block: current_block_height + self.check_interval as u64,
+        });
+    }
+
+    /// Whether `message_id` is the check the loop is waiting for, rather
+    /// than one replaced by a reschedule or left over after `StopChecks`.
+    pub fn is_scheduled_check(&self, message_id: MessageId) -> bool {
+        matches!(self.scheduled_check, Some(check) if check.message_id == message_id)
+    }
+
+    pub fn set_check_interval(
+        &mut self,
+        interval: u32,
+        current_block_height: u64,
+    ) -> Result<(), TmError> {
+        if interval == 0 {
+            return Err(TmError::InvalidCheckInterval);
+        }
+        self.check_interval = interval;
+        self.schedule_check(current_block_height);
+        Ok(())
+    }
+
+    pub fn check_state_flow(&mut self, current_block_height: u64) {
+        if !self.is_active() {
+            debug!("Tamagotchi is no longer active, stopping state checks");
+            self.scheduled_check = None;
+            return;
+        }
+        let events: [TmEvent; 4] = [
+            TmEvent::FeedMe,
+            TmEvent::PlayWithMe,
+            TmEvent::WantToSleep,
+            TmEvent::HealthDropped(self.wellbeing.health),
+        ];
+        if self.pause.is_some() {
+            debug!("Tamagotchi paused, skipping alerts");
+        } else {
+            // Only alerts that are actually sent use up a reservation
+            let alerts: Vec<TmEvent> = events
+                .into_iter()
+                .filter(|event| self.check_mood_in_limit(event) != TmEvent::SelfCheck)
+                .collect();
+            for event in alerts {
+                let Some(reservation_id) = self.reservations.pop() else {
+                    msg::send(self.household.contact(), TmEvent::MakeReservation, 0)
+                        .expect("Error sending make reservation message");
+                    break;
+                };
+                self.send_check_feedback(reservation_id, event);
             }
         }
-        msg::send_delayed(exec::program_id(), TmAction::CheckState, 0, CHECK_INTERVAL)
-            .expect("Error sending delayed message");
+        self.last_checked_health = self.wellbeing.health;
+        self.schedule_check(current_block_height);
     }
 
     fn check_mood_in_limit(&self, mood: &TmEvent) -> TmEvent {
         match mood {
             TmEvent::FeedMe if self.fed <= MOOD_LIMIT => TmEvent::FeedMe,
             TmEvent::PlayWithMe if self.entertained <= MOOD_LIMIT => TmEvent::PlayWithMe,
-            TmEvent::WantToSleep if self.rested <= MOOD_LIMIT => TmEvent::WantToSleep,
+            TmEvent::WantToSleep if self.rested <= MOOD_LIMIT && self.sleeping_until.is_none() => {
+                TmEvent::WantToSleep
+            }
+            TmEvent::HealthDropped(health)
+                if *health + wellbeing::HEALTH_DROP_ALERT <= self.last_checked_health =>
+            {
+                TmEvent::HealthDropped(*health)
+            }
             _ => TmEvent::SelfCheck,
         }
     }
 
     fn send_check_feedback(&self, reservation_id: ReservationId, payload: TmEvent) {
-        msg::send_from_reservation(reservation_id, self.owner, payload, 0)
+        msg::send_from_reservation(reservation_id, self.household.contact(), payload, 0)
             .expect("reply failed on state owner feedback");
     }
 }
 
 #[derive(Encode, Decode, TypeInfo)]
+pub enum TmInit {
+    New {
+        name: String,
+        guardian: Option<ActorId>,
+    },
+    Import(StateEnvelope),
+}
+
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
 pub enum TmAction {
     Name,
     Age,
     Feed,
-    Sleep,
-    Play,
+    Sleep {
+        blocks: u32,
+    },
+    Play(Game),
     Transfer(ActorId),
     Approve(ActorId),
     RevokeApproval,
@@ -199,12 +1505,190 @@ pub enum TmAction {
         store_id: ActorId,
         attribute_id: AttributeId,
     },
-    Owner,
+    Household,
     CheckState,
     ReserveGas {
         reservation_amount: u64,
         duration: u32,
     },
+    ExportState,
+    Migrate {
+        new_program: CodeId,
+    },
+    Withdraw,
+    AllowStore {
+        store_id: ActorId,
+        limit: Option<SpendLimit>,
+    },
+    RemoveStore(ActorId),
+    AllowedStores,
+    SetDelegateBudget {
+        delegate: ActorId,
+        limit: Option<SpendLimit>,
+    },
+    DelegateBudget(ActorId),
+    Stats,
+    /// The renamer must approve the pet to spend the rename fee, if any.
+    Rename(String),
+    SetRenameFee(Option<RenameFee>),
+    SetProfile(Profile),
+    Metadata,
+    Wellbeing,
+    Equip(AttributeId),
+    Unequip(AttributeId),
+    /// Visits `other_pet`, whose owner must have accepted the visit first.
+    Visit {
+        other_pet: ActorId,
+    },
+    AcceptVisit {
+        other_pet: ActorId,
+    },
+    /// Sent by a visiting tamagotchi to its host.
+    HostVisit,
+    Friends,
+    SetHousehold(Household),
+    /// Proposes a guarded action for the household to approve.
+    Propose {
+        action: Box<TmAction>,
+        expires_at: u64,
+    },
+    ApproveProposal(ProposalId),
+    Proposals,
+    /// Guardian only; stops token and ownership moves until `Unpause`.
+    Pause {
+        care_open: bool,
+    },
+    Unpause,
+    /// Subscribes to a seasons registry and pulls its schedule.
+    SubscribeSeasons(ActorId),
+    /// Sent by the seasons registry whenever its schedule changes.
+    SyncSeasons(Vec<SeasonalEvent>),
+    /// Reschedules state checks every `interval` blocks, restarting them if stopped.
+    SetCheckInterval(u32),
+    StopChecks,
+    /// Sent by the tamagotchi to itself when a nap is over.
+    WakeUp {
+        until: u64,
+    },
+    /// Runs synchronous actions in order under a single mood update.
+    Batch {
+        actions: Vec<TmAction>,
+        mode: BatchMode,
+    },
+    /// Commits to an action without showing it; `hash` is its
+    /// `commitment_hash` and `COMMIT_STAKE` must be attached.
+    Commit {
+        hash: [u8; 32],
+    },
+    /// Runs the action committed to before `REVEAL_BLOCKS` pass and returns
+    /// the stake. The reply is the revealed action's own, or its error;
+    /// actions that can't run in a batch can't be revealed either.
+    Reveal {
+        payload: Box<TmAction>,
+        salt: [u8; 32],
+    },
+    /// Opens an insurance policy with `insurer`, paying the first premium.
+    /// Premiums are paid in native value when some is attached, or else from
+    /// the sender's FT, which must approve the pet to spend them.
+    Insure {
+        insurer: ActorId,
+    },
+    PayPremium,
+    /// The current policy and the premium for the next period.
+    Insurance,
+    /// Sent by a lender to offer a loan against the pet. The lender must
+    /// also approve the pet to spend the principal on the token contract.
+    OfferLoan(LoanTerms),
+    CancelLoanOffer,
+    /// Takes a lender's offer; the principal goes to the sender, or to the
+    /// household's contact once a proposal passes, and the pet can't be
+    /// transferred or migrated until the loan is repaid.
+    AcceptLoan {
+        lender: ActorId,
+    },
+    /// Pays the loan back from the sender's FT balance, which must approve
+    /// the pet as a spender of the repayment.
+    RepayLoan,
+    /// Sent by the tamagotchi to itself when a loan is due.
+    LoanDue,
+    /// Splits the pet into shares held by the listed accounts. The holders
+    /// become its household and share its revenue, and it can't change
+    /// hands until it's bought out or one account gathers every share.
+    Fractionalise(Vec<(ActorId, u64)>),
+    TransferShares {
+        to: ActorId,
+        amount: u64,
+    },
+    /// Pays `amount` of the sender's FT out to the shareholders pro rata. The
+    /// sender must approve the pet to spend it.
+    DistributeRevenue(u128),
+    /// Offers to buy every share. The buyer must approve the pet to spend
+    /// the price of the shares it doesn't hold before the vote passes.
+    OfferBuyout {
+        price_per_share: u128,
+    },
+    CancelBuyout,
+    /// Votes for the pending buyout, settling it once voters hold a majority
+    /// of the shares.
+    VoteBuyout,
+    Shares,
+    /// Sends the sender the FT its failed share payouts left with the pet.
+    ClaimTokens,
+}
+
+impl TmAction {
+    /// Whether the action moves tokens, native value or ownership.
+    pub fn moves_assets(&self) -> bool {
+        match self {
+            TmAction::Batch { actions, .. } => actions.iter().any(TmAction::moves_assets),
+            TmAction::Reveal { payload, .. } => payload.moves_assets(),
+            _ => matches!(
+                self,
+                TmAction::Transfer(_)
+                    | TmAction::Approve(_)
+                    | TmAction::SetHousehold(_)
+                    | TmAction::Propose { .. }
+                    | TmAction::ApproveProposal(_)
+                    | TmAction::ApproveTokens { .. }
+                    | TmAction::BuyAttribute { .. }
+                    | TmAction::Withdraw
+                    | TmAction::Rename(_)
+                    | TmAction::Migrate { .. }
+                    | TmAction::Insure { .. }
+                    | TmAction::PayPremium
+                    | TmAction::AcceptLoan { .. }
+                    | TmAction::RepayLoan
+                    | TmAction::Fractionalise(_)
+                    | TmAction::TransferShares { .. }
+                    | TmAction::DistributeRevenue(_)
+                    | TmAction::VoteBuyout
+                    | TmAction::ClaimTokens
+            ),
+        }
+    }
+
+    pub fn is_care(&self) -> bool {
+        match self {
+            TmAction::Batch { actions, .. } => actions.iter().any(TmAction::is_care),
+            TmAction::Reveal { payload, .. } => payload.is_care(),
+            _ => matches!(
+                self,
+                TmAction::Feed
+                    | TmAction::Play(_)
+                    | TmAction::Sleep { .. }
+                    | TmAction::Visit { .. }
+                    | TmAction::HostVisit
+            ),
+        }
+    }
+}
+
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BatchMode {
+    /// The first failing action reverts the whole batch.
+    AllOrNothing,
+    /// Failing actions are reported and the rest still run.
+    BestEffort,
 }
 
 #[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
@@ -218,16 +1702,185 @@ pub enum TmEvent {
     Approve(ActorId),
     RevokeApproval,
     TokenContractSet,
-    TokensApproved { account: ActorId, amount: u128 },
+    TokensApproved {
+        account: ActorId,
+        amount: u128,
+    },
     ApprovalError,
     AttributeBought(AttributeId),
     CompletePrevPurchase(AttributeId),
     ErrorDuringPurchase,
-    Owner(ActorId),
+    Household(Household),
     FeedMe,
     PlayWithMe,
     WantToSleep,
     MakeReservation,
     GasReserved,
     SelfCheck,
+    StateExported(StateEnvelope),
+    Migrated(ActorId),
+    Withdrawn(u128),
+    StoreAllowed(ActorId),
+    StoreRemoved(ActorId),
+    AllowedStores(Vec<(ActorId, Option<u128>)>),
+    DelegateBudgetSet(ActorId),
+    DelegateBudget(Option<u128>),
+    Error(TmError),
+    BatchResult(Vec<Result<TmEvent, TmError>>),
+    Stats(TamagotchiStats),
+    Renamed(String),
+    RenameFeeSet,
+    ProfileSet,
+    Metadata(PetMetadata),
+    WokeUp,
+    Wellbeing(Wellbeing),
+    HealthDropped(u64),
+    Played(GameResult),
+    VisitAccepted(ActorId),
+    VisitHosted {
+        friendship: u64,
+    },
+    Visited {
+        other_pet: ActorId,
+        friendship: u64,
+    },
+    Friends(Vec<(ActorId, u64)>),
+    HouseholdSet,
+    ProposalApproved {
+        id: ProposalId,
+        approvals: u32,
+    },
+    Proposals(Vec<(ProposalId, Proposal)>),
+    Paused,
+    Unpaused,
+    SeasonsSynced,
+    CheckIntervalSet(u32),
+    ChecksStopped,
+    Equipped(AttributeId),
+    Unequipped(AttributeId),
+    Committed {
+        reveal_by: u64,
+    },
+    Insured {
+        paid_until: u64,
+    },
+    PremiumPaid {
+        paid_until: u64,
+    },
+    Insurance {
+        policy: Option<Policy>,
+        premium: u128,
+    },
+    Revived {
+        revivals_left: u32,
+    },
+    Died,
+    LoanOffered(LoanTerms),
+    LoanOfferCancelled,
+    LoanAccepted {
+        lender: ActorId,
+        due_block: u64,
+    },
+    LoanRepaid,
+    LoanDefaulted {
+        lender: ActorId,
+    },
+    Fractionalised {
+        total: u64,
+    },
+    SharesTransferred {
+        to: ActorId,
+        amount: u64,
+    },
+    /// `unpaid` holders' cuts stay with the pet until they claim them.
+    RevenueDistributed {
+        amount: u128,
+        unpaid: Vec<ActorId>,
+    },
+    BuyoutOffered,
+    BuyoutCancelled,
+    /// Shares held by the holders who voted for the buyout so far.
+    BuyoutVoted {
+        votes: u64,
+    },
+    BoughtOut {
+        buyer: ActorId,
+        unpaid: Vec<ActorId>,
+    },
+    Shares(Option<Shares>),
+    TokensClaimed(u128),
+}
+
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
+pub enum TmError {
+    NotMember,
+    NotPermitted,
+    /// Async and message-scheduling actions can't run inside a batch.
+    NotBatchable,
+    BudgetExceeded {
+        remaining: u128,
+    },
+    StoreCapExceeded {
+        remaining: u128,
+    },
+    InvalidName,
+    InvalidProfile,
+    TokenContractNotSet,
+    TokenTransferFailed,
+    AlreadyAsleep,
+    InvalidSleepDuration,
+    OnCooldown {
+        ready_at: u64,
+    },
+    AttributeNotOwned,
+    InvalidMove,
+    VisitNotAccepted,
+    VisitFailed,
+    InvalidHousehold,
+    ProposalRequired,
+    NotProposable,
+    ProposalNotFound,
+    ProposalExpired,
+    NotGuardian,
+    Paused,
+    InvalidCheckInterval,
+    CommitmentPending,
+    StakeRequired,
+    CommitmentNotFound,
+    RevealMismatch,
+    AlreadyInsured,
+    NotInsured,
+    PremiumNotDue,
+    InvalidLoanTerms,
+    LoanOfferNotFound,
+    NoLoan,
+    RepaymentPending,
+    LoanOverdue,
+    /// The pet is collateral for a loan and can't change hands.
+    Collateralized,
+    InvalidShares,
+    NotFractionalised,
+    /// The pet is split into shares and only changes hands by a buyout.
+    Fractionalised,
+    NotShareholder,
+    InsufficientShares,
+    TooManyShareholders,
+    BuyoutPending,
+    NoBuyout,
+    NotBuyer,
+    /// The successor program couldn't be created or rejected the state.
+    MigrationFailed,
+    /// Less native value is credited to the payer than the price.
+    InsufficientValue,
+    /// A token approval or transfer is still waiting on the token contract.
+    TransactionPending,
+    /// A spend limit's period must be at least one block.
+    InvalidPeriod,
+    /// A premium payment is still waiting on the token contract.
+    PremiumPending,
+    /// Revenue is still being paid out to the shareholders.
+    DistributionPending,
+    NothingToClaim,
+    /// Shareholders still have tokens to claim from this program.
+    TokensUnclaimed,
 }
@@ -0,0 +1,40 @@
+use crate::TmError;
+use gstd::{ActorId, Debug, Decode, Encode, MessageId, TypeInfo};
+
+/// Longest a loan can run, which keeps its due check within a `u32` delay.
+pub const MAX_LOAN_BLOCKS: u32 = 1_000_000;
+
+/// What a lender offers: `principal` now against `repayment` within
+/// `duration` blocks of the offer being accepted.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct LoanTerms {
+    pub principal: u128,
+    pub repayment: u128,
+    pub duration: u32,
+}
+
+impl LoanTerms {
+    pub fn validate(&self) -> Result<(), TmError> {
+        if self.principal == 0
+            || self.repayment < self.principal
+            || self.duration == 0
+            || self.duration > MAX_LOAN_BLOCKS
+        {
+            return Err(TmError::InvalidLoanTerms);
+        }
+        Ok(())
+    }
+}
+
+/// A loan the pet is collateral for. Until it's repaid the pet can't change
+/// hands, and once `due_block` passes it goes to the lender.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Loan {
+    pub lender: ActorId,
+    pub terms: LoanTerms,
+    pub due_block: u64,
+    /// The delayed `LoanDue` message that settles a default.
+    pub due_check: MessageId,
+    /// Set while a repayment is waiting for the token contract.
+    pub repaying: bool,
+}
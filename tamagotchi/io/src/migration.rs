@@ -0,0 +1,46 @@
+use crate::{Lifecycle, TamagotchiState};
+use gstd::{prelude::*, ActorId, Debug, Decode, Encode, TypeInfo};
+
+/// Layout version written by `TamagotchiState::export`.
+///
+/// Bump it whenever `TamagotchiState` changes its encoding in a released
+/// contract, and decode the previous layout in `import` so successors can
+/// upgrade it.
+pub const STATE_VERSION: u32 = 1;
+
+/// Versioned, SCALE-encoded pet handed from a retiring program to its successor.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
+pub struct StateEnvelope {
+    pub version: u32,
+    pub state: Vec<u8>,
+}
+
+impl TamagotchiState {
+    pub fn export(&self) -> StateEnvelope {
+        StateEnvelope {
+            version: STATE_VERSION,
+            state: self.encode(),
+        }
+    }
+
+    /// Rebuilds a pet exported by `predecessor`.
+    ///
+    /// Gas reservations belong to the program that made them, so they are
+    /// dropped here and the successor has to reserve gas again.
+    pub fn import(envelope: StateEnvelope, predecessor: ActorId) -> Self {
+        let mut state: TamagotchiState = match envelope.version {
+            STATE_VERSION => {
+                TamagotchiState::decode(&mut envelope.state.as_ref()).expect("Invalid state")
+            }
+            version => panic!("Unsupported state version {}", version),
+        };
+        assert!(
+            state.approve_transaction.is_none(),
+            "Imported state has a pending token approval"
+        );
+        state.reservations.clear();
+        state.lifecycle = Lifecycle::Active;
+        state.predecessor = Some(predecessor);
+        state
+    }
+}
@@ -0,0 +1,104 @@
+use crate::TmError;
+use gstd::{prelude::*, ActorId, Debug, Decode, Encode, TypeInfo};
+
+pub const MAX_NAME_LEN: usize = 32;
+pub const MAX_AVATAR_URI_LEN: usize = 256;
+pub const MAX_DESCRIPTION_LEN: usize = 512;
+pub const MAX_TAGS: usize = 8;
+pub const MAX_TAG_LEN: usize = 24;
+pub const AVATAR_URI_SCHEMES: [&str; 3] = ["https://", "ipfs://", "ar://"];
+
+pub const CHILD_AGE_BLOCKS: u64 = 14_400;
+pub const ADULT_AGE_BLOCKS: u64 = 100_800;
+pub const ELDER_AGE_BLOCKS: u64 = 432_000;
+
+#[derive(Default, Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
+pub struct Profile {
+    pub avatar_uri: Option<String>,
+    pub description: String,
+    pub tags: Vec<String>,
+}
+
+impl Profile {
+    pub fn validate(&self) -> Result<(), TmError> {
+        if let Some(uri) = &self.avatar_uri {
+            if uri.len() > MAX_AVATAR_URI_LEN
+                || !AVATAR_URI_SCHEMES
+                    .iter()
+                    .any(|scheme| uri.starts_with(scheme))
+                || uri.chars().any(|c| c.is_whitespace() || c.is_control())
+            {
+                return Err(TmError::InvalidProfile);
+            }
+        }
+        if self.description.len() > MAX_DESCRIPTION_LEN
+            || self
+                .description
+                .chars()
+                .any(|c| c.is_control() && c != '\n')
+        {
+            return Err(TmError::InvalidProfile);
+        }
+        if self.tags.len() > MAX_TAGS || !self.tags.iter().all(|tag| valid_tag(tag)) {
+            return Err(TmError::InvalidProfile);
+        }
+        Ok(())
+    }
+}
+
+/// Names are 1 to `MAX_NAME_LEN` ASCII letters, digits, spaces, `-` or `_`,
+/// without leading or trailing spaces.
+pub fn validate_name(name: &str) -> Result<(), TmError> {
+    if name.is_empty()
+        || name.len() > MAX_NAME_LEN
+        || name.trim() != name
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, ' ' | '-' | '_'))
+    {
+        return Err(TmError::InvalidName);
+    }
+    Ok(())
+}
+
+fn valid_tag(tag: &str) -> bool {
+    !tag.is_empty()
+        && tag.len() <= MAX_TAG_LEN
+        && tag
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// FT amount the renamer pays `recipient` on every rename, through an
+/// allowance to the pet.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct RenameFee {
+    pub recipient: ActorId,
+    pub amount: u128,
+}
+
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Stage {
+    Baby,
+    Child,
+    Adult,
+    Elder,
+}
+
+impl Stage {
+    pub fn from_age(age_blocks: u64) -> Self {
+        match age_blocks {
+            age if age >= ELDER_AGE_BLOCKS => Stage::Elder,
+            age if age >= ADULT_AGE_BLOCKS => Stage::Adult,
+            age if age >= CHILD_AGE_BLOCKS => Stage::Child,
+            _ => Stage::Baby,
+        }
+    }
+}
+
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
+pub struct PetMetadata {
+    pub name: String,
+    pub profile: Profile,
+    pub stage: Stage,
+}
@@ -0,0 +1,127 @@
+use crate::{Household, TmError};
+use gstd::{prelude::*, ActorId, Debug, Decode, Encode, TypeInfo};
+
+/// Most accounts a pet's shares can be spread over, which bounds the
+/// payouts a distribution or buyout has to send.
+pub const MAX_SHAREHOLDERS: usize = 32;
+
+/// A pet split into shares. The holders make up its household and receive
+/// its revenue pro rata; it changes hands only through a buyout.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
+pub struct Shares {
+    pub total: u64,
+    pub holders: BTreeMap<ActorId, u64>,
+    pub buyout: Option<Buyout>,
+    /// Set while revenue is being paid in and out, which holds the holders
+    /// in place.
+    pub distributing: bool,
+}
+
+/// An offer to buy every share at `price_per_share`, in FT.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
+pub struct Buyout {
+    pub buyer: ActorId,
+    pub price_per_share: u128,
+    /// Holders who voted for the offer; it passes once they hold a majority.
+    pub votes: BTreeSet<ActorId>,
+    /// Set while the buyer's payment is waiting for the token contract.
+    pub settling: bool,
+}
+
+impl Shares {
+    pub fn new(holders: Vec<(ActorId, u64)>) -> Result<Self, TmError> {
+        let count = holders.len();
+        let holders: BTreeMap<_, _> = holders.into_iter().collect();
+        if holders.is_empty()
+            || holders.len() != count
+            || holders.len() > MAX_SHAREHOLDERS
+            || holders.values().any(|held| *held == 0)
+        {
+            return Err(TmError::InvalidShares);
+        }
+        let total = holders
+            .values()
+            .try_fold(0u64, |total, held| total.checked_add(*held))
+            .ok_or(TmError::InvalidShares)?;
+        Ok(Shares {
+            total,
+            holders,
+            buyout: None,
+            distributing: false,
+        })
+    }
+
+    /// The household the holders make up: the largest holder first, as its
+    /// contact, and a majority of holders to pass a proposal.
+    pub fn household(&self) -> Household {
+        let mut holders: Vec<(&ActorId, &u64)> = self.holders.iter().collect();
+        holders.sort_by(|(_, a), (_, b)| b.cmp(a));
+        let members: Vec<ActorId> = holders.into_iter().map(|(holder, _)| *holder).collect();
+        Household {
+            threshold: members.len() as u32 / 2 + 1,
+            members,
+        }
+    }
+
+    pub fn held_by(&self, account: &ActorId) -> u64 {
+        self.holders.get(account).copied().unwrap_or_default()
+    }
+
+    /// Rejects changes to the holders while a buyout or a distribution is
+    /// paying them.
+    pub fn ensure_not_settling(&self) -> Result<(), TmError> {
+        if self.buyout.as_ref().is_some_and(|buyout| buyout.settling) {
+            return Err(TmError::BuyoutPending);
+        }
+        if self.distributing {
+            return Err(TmError::DistributionPending);
+        }
+        Ok(())
+    }
+
+    pub fn transfer(&mut self, from: ActorId, to: ActorId, amount: u64) -> Result<(), TmError> {
+        self.ensure_not_settling()?;
+        let held = self.held_by(&from);
+        if held == 0 {
+            return Err(TmError::NotShareholder);
+        }
+        if amount == 0 || amount > held {
+            return Err(TmError::InsufficientShares);
+        }
+        if !self.holders.contains_key(&to) && self.holders.len() >= MAX_SHAREHOLDERS {
+            return Err(TmError::TooManyShareholders);
+        }
+        if amount == held {
+            self.holders.remove(&from);
+        } else {
+            self.holders.insert(from, held - amount);
+        }
+        *self.holders.entry(to).or_default() += amount;
+        // Departed holders' votes no longer count
+        let holders = &self.holders;
+        if let Some(buyout) = &mut self.buyout {
+            buyout.votes.retain(|voter| holders.contains_key(voter));
+        }
+        Ok(())
+    }
+
+    /// Shares held by the holders who voted for the buyout.
+    pub fn votes(&self) -> u64 {
+        self.buyout.as_ref().map_or(0, |buyout| {
+            buyout.votes.iter().map(|voter| self.held_by(voter)).sum()
+        })
+    }
+
+    pub fn has_majority(&self) -> bool {
+        self.votes() * 2 > self.total
+    }
+
+    /// Each holder's cut of `amount`; what rounding leaves stays with the pet.
+    pub fn payouts(&self, amount: u128) -> Vec<(ActorId, u128)> {
+        self.holders
+            .iter()
+            .map(|(holder, held)| (*holder, amount * *held as u128 / self.total as u128))
+            .filter(|(_, payout)| *payout > 0)
+            .collect()
+    }
+}
@@ -0,0 +1,63 @@
+use gstd::{Debug, Decode, Encode, TypeInfo};
+
+/// Counters kept for the whole life of the pet.
+///
+/// A streak is a run of consecutive blocks in which every mood stayed above
+/// `MOOD_LIMIT`; any neglected block ends it.
+#[derive(Default, Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
+pub struct LifetimeStats {
+    pub feeds: u64,
+    pub plays: u64,
+    pub sleeps: u64,
+    pub neglected_blocks: u64,
+    /// Blocks the ongoing neglect has lasted, zero while the pet is cared for.
+    pub current_neglect: u64,
+    pub current_streak: u64,
+    pub longest_streak: u64,
+    /// Tokens approved to spenders, which purchases then draw on.
+    pub ft_spent: u128,
+    /// Experience earned in games, more for wins than losses.
+    pub xp: u64,
+}
+
+impl LifetimeStats {
+    /// Accounts `elapsed` blocks of which the first `healthy` kept every mood
+    /// above the limit.
+    pub fn record_blocks(&mut self, elapsed: u64, healthy: u64) {
+        if elapsed == 0 {
+            return;
+        }
+        let healthy = healthy.min(elapsed);
+        self.current_streak += healthy;
+        if healthy > 0 {
+            self.current_neglect = 0;
+        }
+        if healthy < elapsed {
+            self.neglected_blocks += elapsed - healthy;
+            self.current_neglect += elapsed - healthy;
+            self.end_streak();
+        }
+    }
+
+    fn end_streak(&mut self) {
+        self.longest_streak = self.longest_streak.max(self.current_streak);
+        self.current_streak = 0;
+    }
+
+    pub fn longest_streak(&self) -> u64 {
+        self.longest_streak.max(self.current_streak)
+    }
+}
+
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
+pub struct TamagotchiStats {
+    pub age_blocks: u64,
+    pub age_ms: u64,
+    pub feeds: u64,
+    pub plays: u64,
+    pub sleeps: u64,
+    pub xp: u64,
+    pub neglected_blocks: u64,
+    pub longest_streak: u64,
+    pub ft_spent: u128,
+}
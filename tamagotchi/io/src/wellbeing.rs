@@ -0,0 +1,59 @@
+use crate::MAX_MOOD_VALUE;
+use gstd::{Debug, Decode, Encode, TypeInfo};
+
+/// Percent each mood contributes to a score; the three add up to 100.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct MoodWeights {
+    pub fed: u64,
+    pub entertained: u64,
+    pub rested: u64,
+}
+
+pub const HAPPINESS_WEIGHTS: MoodWeights = MoodWeights {
+    fed: 25,
+    entertained: 50,
+    rested: 25,
+};
+
+pub const HEALTH_WEIGHTS: MoodWeights = MoodWeights {
+    fed: 50,
+    entertained: 15,
+    rested: 35,
+};
+
+pub const MAX_SCORE: u64 = 100;
+/// Every this many blocks of ongoing neglect take one point off both scores.
+pub const NEGLECT_BLOCKS_PER_POINT: u64 = 60;
+pub const MAX_NEGLECT_PENALTY: u64 = 50;
+pub const BONUS_PER_ATTRIBUTE: u64 = 2;
+pub const MAX_ATTRIBUTE_BONUS: u64 = 10;
+/// Health falling by at least this much between two checks alerts the owner.
+pub const HEALTH_DROP_ALERT: u64 = 10;
+
+#[derive(Default, Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Wellbeing {
+    pub happiness: u64,
+    pub health: u64,
+}
+
+impl Wellbeing {
+    pub fn new(
+        (fed, entertained, rested): (u64, u64, u64),
+        neglected_blocks: u64,
+        equipped_attributes: usize,
+    ) -> Self {
+        let penalty = (neglected_blocks / NEGLECT_BLOCKS_PER_POINT).min(MAX_NEGLECT_PENALTY);
+        let bonus = (equipped_attributes as u64 * BONUS_PER_ATTRIBUTE).min(MAX_ATTRIBUTE_BONUS);
+        let score = |weights: MoodWeights| {
+            let weighted =
+                fed * weights.fed + entertained * weights.entertained + rested * weights.rested;
+            (weighted / MAX_MOOD_VALUE + bonus)
+                .saturating_sub(penalty)
+                .min(MAX_SCORE)
+        };
+        Wellbeing {
+            happiness: score(HAPPINESS_WEIGHTS),
+            health: score(HEALTH_WEIGHTS),
+        }
+    }
+}
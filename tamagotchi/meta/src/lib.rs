@@ -0,0 +1,370 @@
+//! Readable metadata of the tamagotchi program for frontends.
+//!
+//! [`export`] builds the `scale-info` registry of the types named by
+//! `TamagotchiMetadata`, together with an example encoded payload for every
+//! `TmAction` variant. [`check`] compares two exports and lists the changes
+//! that would break a frontend built against the older one.
+
+use gmeta::{Metadata, Types};
+use gstd::{ActorId, CodeId};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::{form::PortableForm, Field, MetaType, PortableRegistry, Registry, TypeDef};
+use seasons_io::{Modifiers, SeasonalEvent};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use tamagotchi_io::{
+    BatchMode, Game, Hand, Household, LoanTerms, Profile, RenameFee, SpendLimit,
+    TamagotchiMetadata, TmAction, STATE_VERSION,
+};
+
+type Program = TamagotchiMetadata;
+
+/// Registry ids of the program's message and state types.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProgramTypes {
+    pub init: u32,
+    pub handle_input: u32,
+    pub handle_output: u32,
+    pub others_output: u32,
+    pub state: u32,
+}
+
+/// A `TmAction` variant and its SCALE encoding as `0x` prefixed hex.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Example {
+    pub variant: String,
+    pub payload: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MetadataExport {
+    pub version: String,
+    pub state_version: u32,
+    pub types: ProgramTypes,
+    pub registry: PortableRegistry,
+    pub examples: Vec<Example>,
+}
+
+pub fn export() -> MetadataExport {
+    let mut registry = Registry::new();
+    let mut register = |ty: MetaType| registry.register_type(&ty).id;
+    let types = ProgramTypes {
+        init: register(input::<<Program as Metadata>::Init>()),
+        handle_input: register(input::<<Program as Metadata>::Handle>()),
+        handle_output: register(output::<<Program as Metadata>::Handle>()),
+        others_output: register(output::<<Program as Metadata>::Others>()),
+        state: register(MetaType::new::<<Program as Metadata>::State>()),
+    };
+    let registry = PortableRegistry::from(registry);
+    let variants = action_variants(&registry, types.handle_input);
+    let examples = examples()
+        .into_iter()
+        .map(|action| {
+            let payload = action.encode();
+            Example {
+                variant: variants[&payload[0]].clone(),
+                payload: format!("0x{}", hex::encode(payload)),
+            }
+        })
+        .collect();
+    MetadataExport {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        state_version: STATE_VERSION,
+        types,
+        registry,
+        examples,
+    }
+}
+
+fn input<T: Types>() -> MetaType {
+    MetaType::new::<T::Input>()
+}
+
+fn output<T: Types>() -> MetaType {
+    MetaType::new::<T::Output>()
+}
+
+fn action_variants(registry: &PortableRegistry, action: u32) -> BTreeMap<u8, String> {
+    match &registry
+        .resolve(action)
+        .expect("TmAction is registered")
+        .type_def
+    {
+        TypeDef::Variant(def) => def
+            .variants
+            .iter()
+            .map(|variant| (variant.index, variant.name.clone()))
+            .collect(),
+        _ => unreachable!("TmAction is an enum"),
+    }
+}
+
+/// Variants of `TmAction` that [`examples`] has no payload for.
+pub fn missing_examples(export: &MetadataExport) -> Vec<String> {
+    let covered: BTreeSet<&str> = export
+        .examples
+        .iter()
+        .map(|example| example.variant.as_str())
+        .collect();
+    action_variants(&export.registry, export.types.handle_input)
+        .into_values()
+        .filter(|variant| !covered.contains(variant.as_str()))
+        .collect()
+}
+
+/// One value of every `TmAction` variant, in declaration order.
+pub fn examples() -> Vec<TmAction> {
+    let account = ActorId::from(1);
+    let store_id = ActorId::from(2);
+    let other_pet = ActorId::from(3);
+    let limit = SpendLimit {
+        amount: 1000,
+        period: 100,
+    };
+    vec![
+        TmAction::Name,
+        TmAction::Age,
+        TmAction::Feed,
+        TmAction::Sleep { blocks: 100 },
+        TmAction::Play(Game::RockPaperScissors(Hand::Rock)),
+        TmAction::Transfer(account),
+        TmAction::Approve(account),
+        TmAction::RevokeApproval,
+        TmAction::SetTokenContract(account),
+        TmAction::ApproveTokens {
+            account,
+            amount: 100,
+        },
+        TmAction::BuyAttribute {
+            store_id,
+            attribute_id: 1,
+        },
+        TmAction::Household,
+        TmAction::CheckState,
+        TmAction::ReserveGas {
+            reservation_amount: 10_000_000_000,
+            duration: 1000,
+        },
+        TmAction::ExportState,
+        TmAction::Migrate {
+            new_program: CodeId::from([7; 32]),
+        },
+        TmAction::Withdraw,
+        TmAction::AllowStore {
+            store_id,
+            limit: Some(limit),
+        },
+        TmAction::RemoveStore(store_id),
+        TmAction::AllowedStores,
+        TmAction::SetDelegateBudget {
+            delegate: account,
+            limit: Some(limit),
+        },
+        TmAction::DelegateBudget(account),
+        TmAction::Stats,
+        TmAction::Rename(String::from("Armando")),
+        TmAction::SetRenameFee(Some(RenameFee {
+            recipient: account,
+            amount: 10,
+        })),
+        TmAction::SetProfile(Profile {
+            avatar_uri: Some(String::from("https://example.com/armando.png")),
+            description: String::from("A very good pet"),
+            tags: vec![String::from("cat")],
+        }),
+        TmAction::Metadata,
+        TmAction::Wellbeing,
+        TmAction::Equip(1),
+        TmAction::Unequip(1),
+        TmAction::Visit { other_pet },
+        TmAction::AcceptVisit { other_pet },
+        TmAction::HostVisit,
+        TmAction::Friends,
+        TmAction::SetHousehold(Household {
+            members: vec![account, store_id],
+            threshold: 2,
+        }),
+        TmAction::Propose {
+            action: Box::new(TmAction::Transfer(account)),
+            expires_at: 1000,
+        },
+        TmAction::ApproveProposal(0),
+        TmAction::Proposals,
+        TmAction::Pause { care_open: true },
+        TmAction::Unpause,
+        TmAction::SubscribeSeasons(account),
+        TmAction::SyncSeasons(vec![SeasonalEvent {
+            id: 0,
+            name: String::from("Winter"),
+            start_block: 100,
+            end_block: 1000,
+            modifiers: Modifiers::default(),
+        }]),
+        TmAction::SetCheckInterval(60),
+        TmAction::StopChecks,
+        TmAction::WakeUp { until: 100 },
+        TmAction::Batch {
+            actions: vec![TmAction::Feed, TmAction::Sleep { blocks: 10 }],
+            mode: BatchMode::AllOrNothing,
+        },
+        TmAction::Commit { hash: [9; 32] },
+        TmAction::Reveal {
+            payload: Box::new(TmAction::Play(Game::GuessTheNumber(3))),
+            salt: [5; 32],
+        },
+        TmAction::Insure { insurer: account },
+        TmAction::PayPremium,
+        TmAction::Insurance,
+        TmAction::OfferLoan(LoanTerms {
+            principal: 1000,
+            repayment: 1100,
+            duration: 100,
+        }),
+        TmAction::CancelLoanOffer,
+        TmAction::AcceptLoan { lender: account },
+        TmAction::RepayLoan,
+        TmAction::LoanDue,
+        TmAction::Fractionalise(vec![(account, 60), (store_id, 40)]),
+        TmAction::TransferShares {
+            to: other_pet,
+            amount: 10,
+        },
+        TmAction::DistributeRevenue(1000),
+        TmAction::OfferBuyout {
+            price_per_share: 50,
+        },
+        TmAction::CancelBuyout,
+        TmAction::VoteBuyout,
+        TmAction::Shares,
+        TmAction::ClaimTokens,
+    ]
+}
+
+/// Lists what in `current` breaks frontends built against `previous`.
+///
+/// Enums may gain variants at the end and the state may gain fields at the
+/// end; any other change to a type's encoding is reported, as is an example
+/// payload of `previous` that no longer decodes to the same variant.
+pub fn check(previous: &MetadataExport, current: &MetadataExport) -> Vec<String> {
+    let mut compat = Compat {
+        previous: &previous.registry,
+        current: &current.registry,
+        compared: BTreeSet::new(),
+        problems: Vec::new(),
+    };
+    let (old, new) = (&previous.types, &current.types);
+    compat.compare("init", old.init, new.init, false);
+    compat.compare("handle input", old.handle_input, new.handle_input, false);
+    compat.compare("handle output", old.handle_output, new.handle_output, false);
+    compat.compare("others output", old.others_output, new.others_output, false);
+    compat.compare("state", old.state, new.state, true);
+    let variants = action_variants(&current.registry, new.handle_input);
+    for example in &previous.examples {
+        let payload = hex::decode(example.payload.trim_start_matches("0x")).unwrap_or_default();
+        match TmAction::decode(&mut payload.as_slice()) {
+            Ok(action) if action.encode() == payload => {
+                if variants.get(&payload[0]) != Some(&example.variant) {
+                    compat.problems.push(format!(
+                        "example {}: now decodes as {:?}",
+                        example.variant,
+                        variants.get(&payload[0])
+                    ));
+                }
+            }
+            _ => compat
+                .problems
+                .push(format!("example {}: no longer decodes", example.variant)),
+        }
+    }
+    compat.problems
+}
+
+struct Compat<'a> {
+    previous: &'a PortableRegistry,
+    current: &'a PortableRegistry,
+    /// Type pairs already compared, which also stops recursive types.
+    compared: BTreeSet<(u32, u32)>,
+    problems: Vec<String>,
+}
+
+impl Compat<'_> {
+    fn compare(&mut self, at: &str, old: u32, new: u32, extensible: bool) {
+        if !self.compared.insert((old, new)) {
+            return;
+        }
+        let (old_type, new_type) = match (self.previous.resolve(old), self.current.resolve(new)) {
+            (Some(old_type), Some(new_type)) => (old_type, new_type),
+            _ => {
+                self.problems.push(format!("{}: type is missing", at));
+                return;
+            }
+        };
+        let at = match new_type.path.segments.last() {
+            Some(name) => format!("{} ({})", at, name),
+            None => at.to_string(),
+        };
+        match (&old_type.type_def, &new_type.type_def) {
+            (TypeDef::Composite(old_def), TypeDef::Composite(new_def)) => {
+                self.compare_fields(&at, &old_def.fields, &new_def.fields, extensible)
+            }
+            (TypeDef::Variant(old_def), TypeDef::Variant(new_def)) => {
+                for old_variant in &old_def.variants {
+                    let at = format!("{}::{}", at, old_variant.name);
+                    match new_def
+                        .variants
+                        .iter()
+                        .find(|variant| variant.index == old_variant.index)
+                    {
+                        Some(new_variant) if new_variant.name == old_variant.name => self
+                            .compare_fields(&at, &old_variant.fields, &new_variant.fields, false),
+                        Some(new_variant) => self
+                            .problems
+                            .push(format!("{}: index now belongs to {}", at, new_variant.name)),
+                        None => self.problems.push(format!("{}: variant was removed", at)),
+                    }
+                }
+            }
+            (TypeDef::Sequence(old_def), TypeDef::Sequence(new_def)) => {
+                self.compare(&at, old_def.type_param.id, new_def.type_param.id, false)
+            }
+            (TypeDef::Array(old_def), TypeDef::Array(new_def)) if old_def.len == new_def.len => {
+                self.compare(&at, old_def.type_param.id, new_def.type_param.id, false)
+            }
+            (TypeDef::Tuple(old_def), TypeDef::Tuple(new_def))
+                if old_def.fields.len() == new_def.fields.len() =>
+            {
+                for (index, (old, new)) in old_def.fields.iter().zip(&new_def.fields).enumerate() {
+                    self.compare(&format!("{}.{}", at, index), old.id, new.id, false);
+                }
+            }
+            (TypeDef::Primitive(old_def), TypeDef::Primitive(new_def)) if old_def == new_def => {}
+            (TypeDef::Compact(old_def), TypeDef::Compact(new_def)) => {
+                self.compare(&at, old_def.type_param.id, new_def.type_param.id, false)
+            }
+            (TypeDef::BitSequence(_), TypeDef::BitSequence(_)) => {}
+            _ => self.problems.push(format!("{}: encoding changed", at)),
+        }
+    }
+
+    fn compare_fields(
+        &mut self,
+        at: &str,
+        old: &[Field<PortableForm>],
+        new: &[Field<PortableForm>],
+        extensible: bool,
+    ) {
+        if new.len() < old.len() || (!extensible && new.len() != old.len()) {
+            self.problems.push(format!(
+                "{}: had {} fields, now {}",
+                at,
+                old.len(),
+                new.len()
+            ));
+            return;
+        }
+        for (index, (old, new)) in old.iter().zip(new).enumerate() {
+            let name = old.name.clone().unwrap_or_else(|| index.to_string());
+            self.compare(&format!("{}.{}", at, name), old.ty.id, new.ty.id, false);
+        }
+    }
+}
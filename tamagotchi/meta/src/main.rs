@@ -0,0 +1,52 @@
+use std::{env, fs, process};
+use tamagotchi_meta::{check, export, missing_examples, MetadataExport};
+
+const USAGE: &str = "usage:
+    tamagotchi-meta export [<metadata.json>]
+    tamagotchi-meta check <previous.json>";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let current = export();
+    let missing = missing_examples(&current);
+    if !missing.is_empty() {
+        eprintln!("No example payload for {}", missing.join(", "));
+        process::exit(1);
+    }
+    match args.as_slice() {
+        ["export"] => println!("{}", to_json(&current)),
+        ["export", path] => write(path, &to_json(&current)),
+        ["check", path] => {
+            let previous = fs::read_to_string(path).unwrap_or_else(|error| fail(error));
+            let previous: MetadataExport =
+                serde_json::from_str(&previous).unwrap_or_else(|error| fail(error));
+            let problems = check(&previous, &current);
+            if !problems.is_empty() {
+                eprintln!("Incompatible with {} (version {}):", path, previous.version);
+                for problem in problems {
+                    eprintln!("    {}", problem);
+                }
+                process::exit(1);
+            }
+            println!("Compatible with {} (version {})", path, previous.version);
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    }
+}
+
+fn to_json(export: &MetadataExport) -> String {
+    serde_json::to_string_pretty(export).expect("Metadata is serializable")
+}
+
+fn write(path: &str, json: &str) {
+    fs::write(path, json).unwrap_or_else(|error| fail(error));
+}
+
+fn fail(error: impl std::fmt::Display) -> ! {
+    eprintln!("{}", error);
+    process::exit(1);
+}
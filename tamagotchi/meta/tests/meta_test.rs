@@ -0,0 +1,67 @@
+use scale_info::{form::PortableForm, TypeDef};
+use tamagotchi_meta::{check, examples, export, missing_examples, MetadataExport};
+
+fn type_def<'a>(export: &'a mut MetadataExport, name: &str) -> &'a mut TypeDef<PortableForm> {
+    &mut export
+        .registry
+        .types
+        .iter_mut()
+        .find(|ty| ty.ty.path.segments.last().map(String::as_str) == Some(name))
+        .expect("Type isn't registered")
+        .ty
+        .type_def
+}
+
+#[test]
+fn meta_examples_cover_every_action() {
+    let export = export();
+    assert!(missing_examples(&export).is_empty());
+    assert_eq!(export.examples.len(), examples().len());
+    assert_eq!(export.examples[2].variant, "Feed");
+    assert_eq!(export.examples[2].payload, "0x02");
+}
+
+#[test]
+fn meta_json_round_trip() {
+    let export = export();
+    let json = serde_json::to_string(&export).expect("Unable to serialize");
+    let read: MetadataExport = serde_json::from_str(&json).expect("Unable to deserialize");
+    assert_eq!(read, export);
+    assert!(check(&read, &export).is_empty());
+}
+
+#[test]
+fn meta_check_allows_additions() {
+    let mut previous = export();
+    //An older program without the last action and the last state field
+    if let TypeDef::Variant(def) = type_def(&mut previous, "TmAction") {
+        def.variants.pop();
+    }
+    if let TypeDef::Composite(def) = type_def(&mut previous, "TamagotchiState") {
+        def.fields.pop();
+    }
+    previous.examples.pop();
+    assert_eq!(check(&previous, &export()), Vec::<String>::new());
+}
+
+#[test]
+fn meta_check_reports_breaking_changes() {
+    let mut previous = export();
+    if let TypeDef::Variant(def) = type_def(&mut previous, "TmAction") {
+        let mut removed = def.variants[0].clone();
+        removed.index = 200;
+        removed.name = String::from("Removed");
+        def.variants.push(removed);
+        def.variants[1].name = String::from("Renamed");
+    }
+    if let TypeDef::Composite(def) = type_def(&mut previous, "Household") {
+        def.fields.pop();
+    }
+    previous.examples[0].payload = String::from("0xff");
+    let problems = check(&previous, &export());
+    let reported = |text: &str| problems.iter().any(|problem| problem.contains(text));
+    assert!(reported("TmAction)::Removed: variant was removed"));
+    assert!(reported("TmAction)::Renamed: index now belongs to Age"));
+    assert!(reported("(Household): had 1 fields, now 2"));
+    assert!(reported("example Name: no longer decodes"));
+}
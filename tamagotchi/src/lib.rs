@@ -1,30 +1,61 @@
 #![no_std]
-use gstd::{debug, exec, msg, prelude::*};
+use gstd::{debug, exec, msg, prelude::*, prog::ProgramGenerator, ActorId, CodeId};
+use seasons_io::{SeasonsAction, SeasonsEvent};
 use store_io::{StoreAction, StoreEvent};
-use tamagotchi_io::{TamagotchiState, TmAction, TmEvent, CHECK_INTERVAL};
+use tamagotchi_io::{
+    BatchMode, Game, Household, Lifecycle, Pause, PetChange, PetEvent, ProposalId, Snapshot,
+    SpendLimit, TamagotchiState, TmAction, TmError, TmEvent, TmInit, CHECK_INTERVAL, VISIT_TIMEOUT,
+};
 
 static mut STATE: Option<TamagotchiState> = None;
 
 #[no_mangle]
 extern "C" fn init() {
     let current_block_height = exec::block_height() as u64;
-    let tamagotchi = TamagotchiState {
-        name: msg::load().expect("no name given"),
-        date_of_birth: exec::block_timestamp(),
-        owner: msg::source(),
-        fed: tamagotchi_io::MIN_MOOD_VALUE,
-        fed_block: current_block_height,
-        entertained: tamagotchi_io::MIN_MOOD_VALUE,
-        entertained_block: current_block_height,
-        rested: tamagotchi_io::MIN_MOOD_VALUE,
-        rested_block: current_block_height,
-        ..Default::default()
+    let mut tamagotchi = match msg::load().expect("no init payload given") {
+        TmInit::New { name, guardian } => {
+            tamagotchi_io::profile::validate_name(&name).expect("invalid name given");
+            TamagotchiState {
+                name,
+                date_of_birth: exec::block_timestamp(),
+                birth_block: current_block_height,
+                household: Household::solo(msg::source()),
+                fed: tamagotchi_io::MIN_MOOD_VALUE,
+                fed_block: current_block_height,
+                entertained: tamagotchi_io::MIN_MOOD_VALUE,
+                entertained_block: current_block_height,
+                rested: tamagotchi_io::MIN_MOOD_VALUE,
+                rested_block: current_block_height,
+                guardian,
+                check_interval: CHECK_INTERVAL,
+                ..Default::default()
+            }
+        }
+        TmInit::Import(envelope) => TamagotchiState::import(envelope, msg::source()),
     };
+    tamagotchi.schedule_check(current_block_height);
+    // A nap's wake-up was scheduled by the predecessor, so it never gets here
+    if let Some(until) = tamagotchi.sleeping_until {
+        let blocks = until.saturating_sub(current_block_height).max(1);
+        msg::send_delayed(
+            exec::program_id(),
+            TmAction::WakeUp { until },
+            0,
+            blocks as u32,
+        )
+        .expect("Failed to send delayed wake up");
+    }
+    emit(
+        current_block_height,
+        PetChange::Born {
+            name: tamagotchi.name.clone(),
+            household: tamagotchi.household.clone(),
+        },
+    );
     debug!("Tamagotchi info: {:?}", tamagotchi);
     unsafe {
         STATE = Some(tamagotchi);
     }
-    msg::send_delayed(exec::program_id(), TmAction::CheckState, 0, CHECK_INTERVAL).expect("Failed to send delayed in init");
 }
 
 #[gstd::async_main]
@@ -32,76 +63,85 @@ async fn main() {
     let action: TmAction = msg::load().expect("no action given");
     let tamagotchi = unsafe { STATE.get_or_insert(Default::default()) };
     let current_block_height: u64 = exec::block_height() as u64;
+    let before = Snapshot::of(tamagotchi);
+    handle(tamagotchi, action, current_block_height).await;
+    for change in before.changes(&Snapshot::of(tamagotchi)) {
+        emit(current_block_height, change);
+    }
+}
+
+async fn handle(tamagotchi: &mut TamagotchiState, action: TmAction, current_block_height: u64) {
     tamagotchi.update_mood(current_block_height);
+    tamagotchi.check_survival(current_block_height);
+    tamagotchi.lapse_insurance(current_block_height);
+    tamagotchi.expire_commitments(current_block_height);
     debug!("Block {:?}", current_block_height);
+    let query = matches!(
+        action,
+        TmAction::Name
+            | TmAction::Age
+            | TmAction::Household
+            | TmAction::Proposals
+            | TmAction::Stats
+            | TmAction::Metadata
+            | TmAction::Wellbeing
+            | TmAction::Friends
+            | TmAction::Insurance
+            | TmAction::Shares
+            | TmAction::AllowedStores
+            | TmAction::DelegateBudget(_)
+            | TmAction::CheckState
+            | TmAction::LoanDue
+    );
+    // Whatever was owed stays withdrawable after the pet dies
+    let withdraw =
+        tamagotchi.is_dead() && matches!(action, TmAction::Withdraw | TmAction::ClaimTokens);
+    assert!(
+        tamagotchi.is_active() || ((query || withdraw) && msg::value() == 0),
+        "Tamagotchi has died, been retired or is migrating"
+    );
+    tamagotchi
+        .ensure_unpaused(&action, msg::value())
+        .unwrap_or_else(|error| panic!("{:?}", error));
+    tamagotchi.deposit(msg::source(), msg::value());
     match action {
-        TmAction::Feed => {
-            assert!(
-                tamagotchi.verify_permission(msg::source()),
-                "Only owner and allowed account can interact with this tamagotchi"
-            );
-            tamagotchi.feed();
-            msg::reply(TmEvent::Fed, 0).expect("reply failed on feed");
-        }
-        TmAction::Play => {
-            assert!(
-                tamagotchi.verify_permission(msg::source()),
-                "Only owner and allowed account can interact with this tamagotchi"
-            );
-            tamagotchi.play();
-            msg::reply(TmEvent::Entertained, 0).expect("reply failed on entertain");
-        }
-        TmAction::Sleep => {
-            assert!(
-                tamagotchi.verify_permission(msg::source()),
-                "Only owner and allowed account can interact with this tamagotchi"
-            );
-            tamagotchi.sleep();
-            msg::reply(TmEvent::Slept, 0).expect("reply failed on sleep");
-        }
-        TmAction::Name => {
-            msg::reply(TmEvent::Name(tamagotchi.name.clone()), 0).expect("reply failed on name");
-        }
-        TmAction::Age => {
-            msg::reply(
-                TmEvent::Age(exec::block_timestamp() - tamagotchi.date_of_birth),
-                0,
-            )
-            .expect("reply failed on age");
+        TmAction::Batch { actions, mode } => {
+            let source = msg::source();
+            let mut results = Vec::with_capacity(actions.len());
+            for (index, action) in actions.into_iter().enumerate() {
+                let result = execute(tamagotchi, source, action, current_block_height);
+                if let (BatchMode::AllOrNothing, Err(error)) = (mode, &result) {
+                    panic!("Batch action {} failed: {:?}", index, error);
+                }
+                results.push(result);
+            }
+            msg::reply(TmEvent::BatchResult(results), 0).expect("reply failed on batch");
         }
-        TmAction::Transfer(new_owner) => {
+        TmAction::BuyAttribute {
+            store_id,
+            attribute_id,
+        } => {
             assert!(
                 tamagotchi.verify_permission(msg::source()),
-                "Only owner can transfer this tamagotchi"
-            );
-            tamagotchi.owner = new_owner;
-        }
-        TmAction::Approve(allowed_account) => {
-            assert!(
-                tamagotchi.verify_ownership(msg::source()),
-                "Only owner can approve an account"
-            );
-            tamagotchi.allowed_account = Some(allowed_account);
-        }
-        TmAction::RevokeApproval => {
-            assert!(
-                tamagotchi.verify_ownership(msg::source()),
-                "Only owner can revoke an account"
+                "Only members and allowed account can interact with this tamagotchi"
             );
-            tamagotchi.allowed_account = None;
-        }
-        TmAction::SetTokenContract(ft_contract) => {
             assert!(
-                tamagotchi.verify_ownership(msg::source()),
-                "Only owner can set the token contract"
+                tamagotchi.verify_store(store_id),
+                "Store is not in the allowed list"
             );
-            tamagotchi.ft_contract = Some(ft_contract);
-            msg::reply(TmEvent::TokenContractSet, 0).expect("reply failed on set token contract");
-        }
-        TmAction::BuyAttribute {
-            store_id,
-            attribute_id,
-        } => {
+            let native = msg::value() > 0;
+            let charged = match tamagotchi.charge_purchase(
+                msg::source(),
+                store_id,
+                current_block_height,
+                native,
+            ) {
+                Ok(charged) => charged,
+                Err(error) => {
+                    msg::reply(TmEvent::Error(error), 0).expect("reply failed on buy attribute");
+                    return;
+                }
+            };
             let result = msg::send_for_reply_as::<_, StoreEvent>(
                 store_id,
                 StoreAction::BuyAttribute { attribute_id },
@@ -111,47 +151,596 @@ async fn main() {
             .expect("Failed to send buy attribute message")
             .await;
             debug!("Successfully sent buy attribute message");
-            if let Ok(StoreEvent::AttributeSold { success }) = result {
-                if success {
-                    debug!("Attribute bought");
-                    msg::reply(TmEvent::AttributeBought(attribute_id), 0)
-                        .expect("reply failed on buy attribute");
-                } else {
-                    debug!("Attribute not bought");
-                    msg::reply(TmEvent::ErrorDuringPurchase, 0)
-                        .expect("reply failed on buy attribute");
-                }
+            // Whatever was charged is held until the sale settles, so an
+            // unexpected reply refunds it rather than panicking
+            let success = matches!(result, Ok(StoreEvent::AttributeSold { success: true }));
+            tamagotchi.settle_purchase(msg::source(), store_id, charged, success, native);
+            if success {
+                debug!("Attribute bought");
+                tamagotchi.attributes.insert(attribute_id);
+                msg::reply(TmEvent::AttributeBought(attribute_id), 0)
+                    .expect("reply failed on buy attribute");
             } else {
-                panic!("Unexpected reply from store");
-            };
+                debug!("Attribute not bought");
+                msg::reply(TmEvent::ErrorDuringPurchase, 0).expect("reply failed on buy attribute");
+            }
         }
         TmAction::ApproveTokens { account, amount } => {
-            assert!(
-                tamagotchi.verify_permission(msg::source()),
-                "Only owner and allowed account can interact with this tamagotchi"
-            );
+            tamagotchi
+                .ensure_direct(msg::source())
+                .and_then(|_| tamagotchi.ensure_whole())
+                .unwrap_or_else(|error| panic!("{:?}", error));
             debug!("Successfully verified permission");
-            let approval_result = tamagotchi.approve_tokens(&account, amount).await;
+            let approval_result = tamagotchi
+                .approve_tokens(msg::source(), &account, amount)
+                .await;
             debug!("Successfully approved tokens");
             msg::reply(approval_result, 0).expect("Error sending approval result");
         }
-        TmAction::Owner => {
-            msg::reply(TmEvent::Owner(tamagotchi.owner), 0).expect("reply failed on owner");
+        TmAction::Propose { action, expires_at } => {
+            let source = msg::source();
+            let approval = tamagotchi
+                .propose(source, *action, expires_at, current_block_height)
+                .and_then(|id| {
+                    tamagotchi
+                        .approve_proposal(source, id, current_block_height)
+                        .map(|ready| (id, ready))
+                });
+            settle_proposal(tamagotchi, source, approval, current_block_height).await;
+        }
+        TmAction::ApproveProposal(id) => {
+            let source = msg::source();
+            let approval = tamagotchi
+                .approve_proposal(source, id, current_block_height)
+                .map(|ready| (id, ready));
+            settle_proposal(tamagotchi, source, approval, current_block_height).await;
+        }
+        TmAction::Visit { other_pet } => {
+            assert!(
+                tamagotchi.verify_permission(msg::source()),
+                "Only members and allowed account can interact with this tamagotchi"
+            );
+            assert!(
+                other_pet != exec::program_id(),
+                "Tamagotchi can't visit itself"
+            );
+            let result = msg::send_for_reply_as::<_, TmEvent>(other_pet, TmAction::HostVisit, 0, 0)
+                .expect("Failed to send visit message")
+                .up_to(Some(VISIT_TIMEOUT))
+                .expect("Invalid visit timeout")
+                .await;
+            let event = match result {
+                Ok(TmEvent::VisitHosted { .. }) => {
+                    let friendship = tamagotchi.enjoy_visit(other_pet);
+                    TmEvent::Visited {
+                        other_pet,
+                        friendship,
+                    }
+                }
+                _ => TmEvent::Error(TmError::VisitFailed),
+            };
+            msg::reply(event, 0).expect("reply failed on visit");
+        }
+        TmAction::SubscribeSeasons(registry) => {
+            tamagotchi
+                .ensure_membership(msg::source())
+                .unwrap_or_else(|error| panic!("{:?}", error));
+            let result =
+                msg::send_for_reply_as::<_, SeasonsEvent>(registry, SeasonsAction::Subscribe, 0, 0)
+                    .expect("Failed to send subscribe message")
+                    .await;
+            if let Ok(SeasonsEvent::Subscribed(events)) = result {
+                tamagotchi.season_registry = Some(registry);
+                tamagotchi.seasonal_events = events;
+                msg::reply(TmEvent::SeasonsSynced, 0).expect("reply failed on subscribe");
+            } else {
+                panic!("Unexpected reply from seasons registry");
+            }
         }
         TmAction::CheckState => {
-            tamagotchi.check_state_flow();
+            assert_eq!(
+                msg::source(),
+                exec::program_id(),
+                "Only the tamagotchi itself can check its state"
+            );
+            if tamagotchi.is_scheduled_check(msg::id()) {
+                tamagotchi.check_state_flow(current_block_height);
+            } else {
+                debug!("Ignoring a check that is no longer scheduled");
+            }
         }
         TmAction::ReserveGas {
             reservation_amount,
             duration,
         } => {
+            // The check loop started in init picks the reservation up
             msg::reply(tamagotchi.make_reservation(reservation_amount, duration), 0)
                 .expect("reply failed on reserve gas");
-            msg::send_delayed(exec::program_id(), TmAction::CheckState, 0, CHECK_INTERVAL).expect("Failed to send delayed in reserve gas");
+        }
+        TmAction::Withdraw => {
+            let amount = tamagotchi.withdraw(msg::source());
+            assert!(amount > 0, "Nothing to withdraw");
+            msg::reply(TmEvent::Withdrawn(amount), amount).expect("reply failed on withdraw");
+        }
+        TmAction::Migrate { new_program } => {
+            tamagotchi
+                .ensure_direct(msg::source())
+                .and_then(|_| tamagotchi.ensure_migratable())
+                .unwrap_or_else(|error| panic!("{:?}", error));
+            let event = migrate(tamagotchi, new_program).await;
+            msg::reply(event, 0).expect("reply failed on migrate");
+        }
+        TmAction::WakeUp { until } => {
+            assert_eq!(
+                msg::source(),
+                exec::program_id(),
+                "Only the tamagotchi can wake itself up"
+            );
+            if tamagotchi.wake_up(until) {
+                msg::send(tamagotchi.household.contact(), TmEvent::WokeUp, 0)
+                    .expect("Failed to send wake up");
+            }
+        }
+        TmAction::AcceptLoan { lender } => {
+            let source = msg::source();
+            tamagotchi
+                .ensure_direct(source)
+                .unwrap_or_else(|error| panic!("{:?}", error));
+            let event = tamagotchi
+                .accept_loan(source, lender, current_block_height)
+                .await
+                .unwrap_or_else(TmEvent::Error);
+            msg::reply(event, 0).expect("reply failed on accept loan");
+        }
+        TmAction::RepayLoan => {
+            let event = tamagotchi
+                .repay_loan(msg::source(), current_block_height)
+                .await
+                .unwrap_or_else(TmEvent::Error);
+            msg::reply(event, 0).expect("reply failed on repay loan");
+        }
+        TmAction::LoanDue => {
+            assert_eq!(
+                msg::source(),
+                exec::program_id(),
+                "Only the tamagotchi can settle its loan"
+            );
+            tamagotchi.settle_loan_due(msg::id());
+        }
+        TmAction::DistributeRevenue(amount) => {
+            let event = tamagotchi
+                .distribute_revenue(msg::source(), amount)
+                .await
+                .unwrap_or_else(TmEvent::Error);
+            msg::reply(event, 0).expect("reply failed on distribute revenue");
+        }
+        TmAction::ClaimTokens => {
+            let event = tamagotchi
+                .claim_tokens(msg::source())
+                .await
+                .unwrap_or_else(TmEvent::Error);
+            msg::reply(event, 0).expect("reply failed on claim tokens");
+        }
+        TmAction::VoteBuyout => {
+            let event = tamagotchi
+                .vote_buyout(msg::source())
+                .await
+                .unwrap_or_else(TmEvent::Error);
+            msg::reply(event, 0).expect("reply failed on vote buyout");
+        }
+        TmAction::Insure { insurer } => {
+            tamagotchi
+                .ensure_direct(msg::source())
+                .unwrap_or_else(|error| panic!("{:?}", error));
+            let event = tamagotchi
+                .insure(
+                    msg::source(),
+                    insurer,
+                    current_block_height,
+                    msg::value() > 0,
+                )
+                .await
+                .unwrap_or_else(TmEvent::Error);
+            msg::reply(event, 0).expect("reply failed on insure");
+        }
+        TmAction::PayPremium => {
+            tamagotchi
+                .ensure_membership(msg::source())
+                .unwrap_or_else(|error| panic!("{:?}", error));
+            let event = tamagotchi
+                .pay_premium(msg::source(), current_block_height, msg::value() > 0)
+                .await
+                .unwrap_or_else(TmEvent::Error);
+            msg::reply(event, 0).expect("reply failed on pay premium");
+        }
+        TmAction::Rename(name) => {
+            tamagotchi
+                .ensure_membership(msg::source())
+                .unwrap_or_else(|error| panic!("{:?}", error));
+            let event = tamagotchi
+                .rename(msg::source(), name)
+                .await
+                .unwrap_or_else(TmEvent::Error);
+            msg::reply(event, 0).expect("reply failed on rename");
+        }
+        TmAction::Reveal { payload, salt } => {
+            let source = msg::source();
+            tamagotchi
+                .reveal(source, &payload, &salt)
+                .unwrap_or_else(|error| panic!("{:?}", error));
+            // The stake is returned even if the revealed action fails, so
+            // its error is replied instead of panicking
+            let event = execute(tamagotchi, source, *payload, current_block_height)
+                .unwrap_or_else(TmEvent::Error);
+            msg::reply(event, 0).expect("reply failed on reveal");
+        }
+        action => {
+            let event = execute(tamagotchi, msg::source(), action, current_block_height)
+                .unwrap_or_else(|error| panic!("{:?}", error));
+            msg::reply(event, 0).expect("reply failed");
+        }
+    }
+}
+
+/// Replies to a proposal approval, running the proposed action once the
+/// household has agreed to it.
+async fn settle_proposal(
+    tamagotchi: &mut TamagotchiState,
+    source: ActorId,
+    approval: Result<(ProposalId, Option<TmAction>), TmError>,
+    current_block_height: u64,
+) {
+    let event = match approval {
+        Ok((_, Some(TmAction::ApproveTokens { account, amount }))) => {
+            match tamagotchi.ensure_whole() {
+                Ok(()) => tamagotchi.approve_tokens(source, &account, amount).await,
+                Err(error) => TmEvent::Error(error),
+            }
+        }
+        Ok((_, Some(TmAction::Fractionalise(holders)))) => {
+            match tamagotchi.fractionalise(holders) {
+                Ok(total) => TmEvent::Fractionalised { total },
+                Err(error) => TmEvent::Error(error),
+            }
+        }
+        Ok((_, Some(TmAction::Transfer(new_owner)))) => match tamagotchi.ensure_unencumbered() {
+            Ok(()) => {
+                tamagotchi.set_household(Household::solo(new_owner));
+                TmEvent::Transfer(new_owner)
+            }
+            Err(error) => TmEvent::Error(error),
+        },
+        Ok((_, Some(TmAction::SetHousehold(household)))) => {
+            match tamagotchi.ensure_unencumbered() {
+                Ok(()) => {
+                    tamagotchi.set_household(household);
+                    TmEvent::HouseholdSet
+                }
+                Err(error) => TmEvent::Error(error),
+            }
+        }
+        Ok((_, Some(TmAction::SetRenameFee(rename_fee)))) => {
+            tamagotchi.rename_fee = rename_fee;
+            TmEvent::RenameFeeSet
+        }
+        Ok((_, Some(TmAction::SetTokenContract(ft_contract)))) => match tamagotchi.ensure_whole() {
+            Ok(()) => {
+                tamagotchi.ft_contract = Some(ft_contract);
+                TmEvent::TokenContractSet
+            }
+            Err(error) => TmEvent::Error(error),
+        },
+        Ok((_, Some(TmAction::Insure { insurer }))) => tamagotchi
+            .insure(source, insurer, current_block_height, msg::value() > 0)
+            .await
+            .unwrap_or_else(TmEvent::Error),
+        Ok((_, Some(TmAction::AcceptLoan { lender }))) => {
+            let borrower = tamagotchi.household.contact();
+            tamagotchi
+                .accept_loan(borrower, lender, current_block_height)
+                .await
+                .unwrap_or_else(TmEvent::Error)
+        }
+        Ok((_, Some(TmAction::Migrate { new_program }))) => match tamagotchi.ensure_migratable() {
+            Ok(()) => migrate(tamagotchi, new_program).await,
+            Err(error) => TmEvent::Error(error),
+        },
+        Ok((_, Some(action))) => unreachable!("{:?} can't be proposed", action),
+        Ok((id, None)) => TmEvent::ProposalApproved {
+            id,
+            approvals: tamagotchi.proposals[&id].approvals.len() as u32,
+        },
+        Err(error) => TmEvent::Error(error),
+    };
+    msg::reply(event, 0).expect("reply failed on proposal");
+}
+
+/// Hands the pet over to a new program created from `new_program`, which
+/// imports its state and the native value it owes.
+async fn migrate(tamagotchi: &mut TamagotchiState, new_program: CodeId) -> TmEvent {
+    let envelope = tamagotchi.export();
+    let held = tamagotchi.held_value();
+    tamagotchi.lifecycle = Lifecycle::Migrating;
+    let result = ProgramGenerator::create_program_for_reply_as::<_, ()>(
+        new_program,
+        TmInit::Import(envelope),
+        held,
+        0,
+    )
+    .expect("Failed to create successor program")
+    .await;
+    match result {
+        Ok((successor, _)) => {
+            debug!("Tamagotchi migrated to {:?}", successor);
+            for reservation_id in tamagotchi.reservations.drain(..) {
+                let _ = reservation_id.unreserve();
+            }
+            // The successor refunds or forfeits the stakes from now on
+            tamagotchi.owed.clear();
+            tamagotchi.commitments.clear();
+            tamagotchi.lifecycle = Lifecycle::Retired { successor };
+            TmEvent::Migrated(successor)
+        }
+        Err(_) => {
+            // Migrating was saved at the await, so panicking here would
+            // leave the pet stuck in it
+            tamagotchi.lifecycle = Lifecycle::Active;
+            TmEvent::Error(TmError::MigrationFailed)
         }
     }
 }
 
+/// Sends a change to `ActorId::zero()`, where nothing handles it, so it only
+/// ends up in the message log for indexers to pick up.
+fn emit(block: u64, change: PetChange) {
+    msg::send(ActorId::zero(), PetEvent { block, change }, 0).expect("Failed to emit pet event");
+}
+
+/// Randomness for the pet's game moves, mixed by the runtime with the
+/// randomness of the block this message runs in.
+fn random_seed() -> [u8; 32] {
+    let (seed, _) = exec::random(msg::id().into()).expect("Failed to get randomness");
+    seed
+}
+
+/// Runs an action that only touches the pet's own state, so it can be
+/// replied to directly or be part of a batch.
+///
+/// Every other action is handled in `handle`. Those that change state before
+/// awaiting a reply must reply with their errors rather than panic, as a
+/// panic after the await wouldn't undo those changes.
+fn execute(
+    tamagotchi: &mut TamagotchiState,
+    source: ActorId,
+    action: TmAction,
+    current_block_height: u64,
+) -> Result<TmEvent, TmError> {
+    let event = match action {
+        TmAction::Feed => {
+            tamagotchi.ensure_permission(source)?;
+            tamagotchi.feed(current_block_height)?;
+            TmEvent::Fed
+        }
+        TmAction::Play(game) => {
+            tamagotchi.ensure_permission(source)?;
+            let result = tamagotchi.play(game, random_seed(), current_block_height)?;
+            match game {
+                Game::Fetch => TmEvent::Entertained,
+                _ => TmEvent::Played(result),
+            }
+        }
+        TmAction::Sleep { blocks } => {
+            tamagotchi.ensure_permission(source)?;
+            let until = tamagotchi.sleep(blocks, current_block_height)?;
+            msg::send_delayed(exec::program_id(), TmAction::WakeUp { until }, 0, blocks)
+                .expect("Failed to send delayed wake up");
+            TmEvent::Slept
+        }
+        TmAction::Name => TmEvent::Name(tamagotchi.name.clone()),
+        TmAction::Age => TmEvent::Age(exec::block_timestamp() - tamagotchi.date_of_birth),
+        TmAction::Transfer(new_owner) => {
+            tamagotchi.ensure_direct(source)?;
+            tamagotchi.ensure_unencumbered()?;
+            tamagotchi.set_household(Household::solo(new_owner));
+            TmEvent::Transfer(new_owner)
+        }
+        TmAction::SetHousehold(household) => {
+            tamagotchi.ensure_direct(source)?;
+            tamagotchi.ensure_unencumbered()?;
+            household.validate()?;
+            tamagotchi.set_household(household);
+            TmEvent::HouseholdSet
+        }
+        TmAction::Approve(allowed_account) => {
+            tamagotchi.ensure_membership(source)?;
+            tamagotchi.allowed_account = Some(allowed_account);
+            TmEvent::Approve(allowed_account)
+        }
+        TmAction::RevokeApproval => {
+            tamagotchi.ensure_membership(source)?;
+            tamagotchi.allowed_account = None;
+            TmEvent::RevokeApproval
+        }
+        TmAction::SetTokenContract(ft_contract) => {
+            tamagotchi.ensure_direct(source)?;
+            tamagotchi.ensure_whole()?;
+            tamagotchi.ft_contract = Some(ft_contract);
+            TmEvent::TokenContractSet
+        }
+        TmAction::Household => TmEvent::Household(tamagotchi.household.clone()),
+        TmAction::Proposals => TmEvent::Proposals(
+            tamagotchi
+                .proposals
+                .iter()
+                .map(|(id, proposal)| (*id, proposal.clone()))
+                .collect(),
+        ),
+        TmAction::Stats => {
+            TmEvent::Stats(tamagotchi.lifetime_stats(current_block_height, exec::block_timestamp()))
+        }
+        TmAction::AllowStore { store_id, limit } => {
+            tamagotchi.ensure_membership(source)?;
+            limit.as_ref().map_or(Ok(()), SpendLimit::validate)?;
+            tamagotchi.allow_store(store_id, limit, current_block_height);
+            TmEvent::StoreAllowed(store_id)
+        }
+        TmAction::RemoveStore(store_id) => {
+            tamagotchi.ensure_membership(source)?;
+            tamagotchi.allowed_stores.remove(&store_id);
+            TmEvent::StoreRemoved(store_id)
+        }
+        TmAction::AllowedStores => {
+            TmEvent::AllowedStores(tamagotchi.store_budgets(current_block_height))
+        }
+        TmAction::SetDelegateBudget { delegate, limit } => {
+            tamagotchi.ensure_membership(source)?;
+            limit.as_ref().map_or(Ok(()), SpendLimit::validate)?;
+            tamagotchi.set_delegate_budget(delegate, limit, current_block_height);
+            TmEvent::DelegateBudgetSet(delegate)
+        }
+        TmAction::DelegateBudget(delegate) => TmEvent::DelegateBudget(
+            tamagotchi
+                .delegate_budgets
+                .get(&delegate)
+                .map(|budget| budget.remaining(current_block_height)),
+        ),
+        TmAction::ExportState => {
+            tamagotchi.ensure_membership(source)?;
+            TmEvent::StateExported(tamagotchi.export())
+        }
+        TmAction::SetRenameFee(rename_fee) => {
+            tamagotchi.ensure_direct(source)?;
+            tamagotchi.rename_fee = rename_fee;
+            TmEvent::RenameFeeSet
+        }
+        TmAction::SetProfile(profile) => {
+            tamagotchi.ensure_membership(source)?;
+            profile.validate()?;
+            tamagotchi.profile = profile;
+            TmEvent::ProfileSet
+        }
+        TmAction::Metadata => TmEvent::Metadata(tamagotchi.metadata(current_block_height)),
+        TmAction::Wellbeing => TmEvent::Wellbeing(tamagotchi.wellbeing),
+        TmAction::Insurance => TmEvent::Insurance {
+            policy: tamagotchi.insurance,
+            premium: tamagotchi.premium(),
+        },
+        TmAction::Pause { care_open } => {
+            tamagotchi.ensure_guardian(source)?;
+            // Changing what stays open doesn't restart the pause
+            let since = tamagotchi
+                .pause
+                .map_or(current_block_height, |pause| pause.since);
+            tamagotchi.pause = Some(Pause { since, care_open });
+            TmEvent::Paused
+        }
+        TmAction::SyncSeasons(events) => {
+            if tamagotchi.season_registry != Some(source) {
+                return Err(TmError::NotPermitted);
+            }
+            tamagotchi.seasonal_events = events;
+            TmEvent::SeasonsSynced
+        }
+        TmAction::SetCheckInterval(interval) => {
+            tamagotchi.ensure_membership(source)?;
+            tamagotchi.set_check_interval(interval, current_block_height)?;
+            TmEvent::CheckIntervalSet(interval)
+        }
+        TmAction::StopChecks => {
+            tamagotchi.ensure_membership(source)?;
+            tamagotchi.scheduled_check = None;
+            TmEvent::ChecksStopped
+        }
+        TmAction::Unpause => {
+            tamagotchi.ensure_guardian(source)?;
+            tamagotchi.unpause(current_block_height);
+            TmEvent::Unpaused
+        }
+        TmAction::AcceptVisit { other_pet } => {
+            tamagotchi.ensure_membership(source)?;
+            tamagotchi.accept_visit(other_pet, current_block_height);
+            TmEvent::VisitAccepted(other_pet)
+        }
+        TmAction::HostVisit => {
+            let friendship = tamagotchi.host_visit(source, current_block_height)?;
+            TmEvent::VisitHosted { friendship }
+        }
+        TmAction::Friends => TmEvent::Friends(
+            tamagotchi
+                .friends
+                .iter()
+                .map(|(friend, friendship)| (*friend, *friendship))
+                .collect(),
+        ),
+        TmAction::Equip(attribute_id) => {
+            tamagotchi.ensure_permission(source)?;
+            tamagotchi.equip(attribute_id)?;
+            TmEvent::Equipped(attribute_id)
+        }
+        TmAction::Unequip(attribute_id) => {
+            tamagotchi.ensure_permission(source)?;
+            tamagotchi.unequip(attribute_id);
+            TmEvent::Unequipped(attribute_id)
+        }
+        TmAction::Commit { hash } => {
+            let reveal_by = tamagotchi.commit(source, hash, current_block_height)?;
+            TmEvent::Committed { reveal_by }
+        }
+        TmAction::OfferLoan(terms) => {
+            tamagotchi.offer_loan(source, terms)?;
+            TmEvent::LoanOffered(terms)
+        }
+        TmAction::CancelLoanOffer => {
+            tamagotchi
+                .loan_offers
+                .remove(&source)
+                .ok_or(TmError::LoanOfferNotFound)?;
+            TmEvent::LoanOfferCancelled
+        }
+        TmAction::Fractionalise(holders) => {
+            tamagotchi.ensure_direct(source)?;
+            let total = tamagotchi.fractionalise(holders)?;
+            TmEvent::Fractionalised { total }
+        }
+        TmAction::TransferShares { to, amount } => {
+            tamagotchi.transfer_shares(source, to, amount)?;
+            TmEvent::SharesTransferred { to, amount }
+        }
+        TmAction::OfferBuyout { price_per_share } => {
+            tamagotchi.offer_buyout(source, price_per_share)?;
+            TmEvent::BuyoutOffered
+        }
+        TmAction::CancelBuyout => {
+            tamagotchi.cancel_buyout(source)?;
+            TmEvent::BuyoutCancelled
+        }
+        TmAction::Shares => TmEvent::Shares(tamagotchi.shares.clone()),
+        TmAction::BuyAttribute { .. }
+        | TmAction::ApproveTokens { .. }
+        | TmAction::Visit { .. }
+        | TmAction::SubscribeSeasons(_)
+        | TmAction::Propose { .. }
+        | TmAction::ApproveProposal(_)
+        | TmAction::Rename(_)
+        | TmAction::Reveal { .. }
+        | TmAction::Insure { .. }
+        | TmAction::PayPremium
+        | TmAction::AcceptLoan { .. }
+        | TmAction::RepayLoan
+        | TmAction::LoanDue
+        | TmAction::DistributeRevenue(_)
+        | TmAction::VoteBuyout
+        | TmAction::ClaimTokens
+        | TmAction::WakeUp { .. }
+        | TmAction::CheckState
+        | TmAction::ReserveGas { .. }
+        | TmAction::Withdraw
+        | TmAction::Migrate { .. }
+        | TmAction::Batch { .. } => return Err(TmError::NotBatchable),
+    };
+    Ok(event)
+}
+
 #[no_mangle]
 extern "C" fn state() {
     let tamagotchi = unsafe { STATE.as_ref().expect("tamagotchi not initialized") };
@@ -0,0 +1,141 @@
+use gstd::{prelude::*, ActorId};
+use gtest::{Program, RunResult, System};
+use tamagotchi_io::{TamagotchiState, TmAction, TmEvent, TmInit, CHECK_INTERVAL};
+
+const OWNER: u64 = 100;
+const TAMAGOTCHI: u64 = 1;
+
+const RESERVATION_AMOUNT: u64 = 100_000_000;
+const RESERVATION_DURATION: u32 = 2_000;
+/// Moods start at the minimum, so every check sends three alerts and uses
+/// a reservation for each.
+const RESERVATIONS_PER_CHECK: usize = 3;
+
+/// A tamagotchi left alone while blocks go by, and what reaches its owner.
+struct Scenario {
+    sys: System,
+}
+
+impl Scenario {
+    fn new() -> Self {
+        let sys = System::new();
+        let program = Program::current_with_id(&sys, TAMAGOTCHI);
+        let res = program.send(
+            OWNER,
+            TmInit::New {
+                name: String::from("Armando"),
+                guardian: None,
+            },
+        );
+        assert!(!res.main_failed());
+        Scenario { sys }
+    }
+
+    fn reserve_gas(&self, times: usize) {
+        let program = self.sys.get_program(TAMAGOTCHI);
+        for _ in 0..times {
+            let res = program.send(
+                OWNER,
+                TmAction::ReserveGas {
+                    reservation_amount: RESERVATION_AMOUNT,
+                    duration: RESERVATION_DURATION,
+                },
+            );
+            assert!(!res.main_failed());
+        }
+    }
+
+    /// Spends `periods` check intervals and returns the messages sent to the owner.
+    fn advance(&self, periods: u32) -> Vec<TmEvent> {
+        owner_messages(&self.sys.spend_blocks(periods * CHECK_INTERVAL))
+    }
+
+    fn reservations(&self) -> usize {
+        let state: TamagotchiState = self
+            .sys
+            .get_program(TAMAGOTCHI)
+            .read_state()
+            .expect("File reading state");
+        state.reservations.len()
+    }
+}
+
+fn owner_messages(results: &[RunResult]) -> Vec<TmEvent> {
+    let owner: ActorId = OWNER.into();
+    results
+        .iter()
+        .flat_map(|res| res.log())
+        .filter(|log| log.destination() == owner)
+        .map(|log| TmEvent::decode(&mut log.payload()).expect("Unable to decode owner message"))
+        .collect()
+}
+
+#[test]
+fn check_state_alerts_owner() {
+    let scenario = Scenario::new();
+    let periods = 10;
+    scenario.reserve_gas(RESERVATIONS_PER_CHECK * periods as usize);
+    let messages = scenario.advance(periods);
+    let expected: Vec<TmEvent> = (0..periods)
+        .flat_map(|_| [TmEvent::FeedMe, TmEvent::PlayWithMe, TmEvent::WantToSleep])
+        .collect();
+    assert_eq!(messages, expected);
+    assert_eq!(scenario.reservations(), 0);
+    //With the pool empty the owner is asked for more gas
+    let messages = scenario.advance(1);
+    assert_eq!(messages, vec![TmEvent::MakeReservation]);
+}
+
+#[test]
+fn check_state_runs_a_single_loop() {
+    let scenario = Scenario::new();
+    //Every reservation used to start another check loop
+    let reservations = RESERVATIONS_PER_CHECK * 3;
+    scenario.reserve_gas(reservations);
+    for period in 1..=3 {
+        scenario.advance(1);
+        assert_eq!(
+            scenario.reservations(),
+            reservations - RESERVATIONS_PER_CHECK * period
+        );
+    }
+}
+
+#[test]
+fn check_state_schedule_is_controlled_by_owner() {
+    let scenario = Scenario::new();
+    let program = scenario.sys.get_program(TAMAGOTCHI);
+    scenario.reserve_gas(RESERVATIONS_PER_CHECK * 4);
+    //Only the tamagotchi schedules its own checks
+    let res = program.send(OWNER, TmAction::CheckState);
+    assert!(res.main_failed());
+    let res = program.send(OWNER, TmAction::StopChecks);
+    assert!(!res.main_failed());
+    assert!(scenario.advance(2).is_empty());
+    assert_eq!(scenario.reservations(), RESERVATIONS_PER_CHECK * 4);
+    let res = program.send(OWNER, TmAction::SetCheckInterval(0));
+    assert!(res.main_failed());
+    let res = program.send(OWNER, TmAction::SetCheckInterval(CHECK_INTERVAL / 2));
+    assert!(!res.main_failed());
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    let check = state.scheduled_check.expect("Checks not restarted");
+    assert_eq!(check.due_block, state.fed_block + CHECK_INTERVAL as u64 / 2);
+    //Twice as often as before
+    scenario.advance(1);
+    assert_eq!(scenario.reservations(), RESERVATIONS_PER_CHECK * 2);
+}
+
+#[test]
+fn check_state_reserves_only_for_alerts_sent() {
+    let scenario = Scenario::new();
+    let program = scenario.sys.get_program(TAMAGOTCHI);
+    scenario.reserve_gas(RESERVATIONS_PER_CHECK);
+    //A fed pet doesn't ask for food, and keeps that reservation
+    for _ in 0..2 {
+        let res = program.send(OWNER, TmAction::Feed);
+        assert!(!res.main_failed());
+    }
+    let messages = scenario.advance(1);
+    assert_eq!(messages, vec![TmEvent::PlayWithMe, TmEvent::WantToSleep]);
+    assert_eq!(scenario.reservations(), 1);
+}
@@ -0,0 +1,58 @@
+use futures_executor::block_on;
+use gstd::ActorId;
+use gtest::{Program, System};
+use tamagotchi_client::{ClientError, GtestTransport, TamagotchiClient};
+use tamagotchi_io::{Game, Household, TmAction, TmError, TmInit};
+
+const OWNER: u64 = 100;
+const STRANGER: u64 = 101;
+
+fn init_tamagotchi(sys: &System) -> Program {
+    let program = Program::current(sys);
+    let res = program.send(
+        OWNER,
+        TmInit::New {
+            name: String::from("Armando"),
+            guardian: None,
+        },
+    );
+    assert!(!res.main_failed());
+    program
+}
+
+#[test]
+fn client_calls() {
+    let sys = System::new();
+    let program = init_tamagotchi(&sys);
+    let owner = TamagotchiClient::new(GtestTransport::new(&program, OWNER));
+    let stranger = TamagotchiClient::new(GtestTransport::new(&program, STRANGER));
+    block_on(async {
+        assert_eq!(owner.name().await.unwrap(), "Armando");
+        owner.feed().await.unwrap();
+        assert_eq!(owner.play(Game::Fetch).await.unwrap(), None);
+        //Panics and rejections both come back as errors
+        assert!(matches!(
+            stranger.feed().await,
+            Err(ClientError::Panicked(_))
+        ));
+        assert!(matches!(
+            owner.withdraw().await,
+            Err(ClientError::Panicked(_))
+        ));
+        assert!(matches!(
+            owner
+                .call(TmAction::Propose {
+                    action: Box::new(TmAction::Feed),
+                    expires_at: 1_000,
+                })
+                .await,
+            Err(ClientError::Rejected(TmError::NotProposable))
+        ));
+        assert_eq!(
+            owner.household().await.unwrap(),
+            Household::solo(ActorId::from(OWNER))
+        );
+        let state = owner.state().await.unwrap();
+        assert_eq!(state.stats.feeds, 1);
+    });
+}
@@ -0,0 +1,220 @@
+use parity_scale_codec::Encode;
+use proptest::prelude::*;
+use seasons_io::{Modifiers, SeasonalEvent};
+use tamagotchi_io::{
+    Game, Hand, TamagotchiState, BOREDOM_PER_BLOCK, ENERGY_PER_BLOCK, HUNGER_PER_BLOCK,
+    MAX_MOOD_VALUE, MOOD_LIMIT,
+};
+
+/// Keeps block arithmetic far from overflowing while still covering long gaps.
+const MAX_BLOCK: u64 = 1 << 40;
+
+#[derive(Debug, Clone)]
+enum Step {
+    Wait(u64),
+    Feed,
+    Play(Game, [u8; 32]),
+    Sleep(u32),
+    Visit,
+}
+
+fn game() -> impl Strategy<Value = Game> {
+    prop_oneof![
+        Just(Game::Fetch),
+        Just(Game::RockPaperScissors(Hand::Rock)),
+        Just(Game::RockPaperScissors(Hand::Paper)),
+        Just(Game::RockPaperScissors(Hand::Scissors)),
+        (0..=12u8).prop_map(Game::GuessTheNumber),
+    ]
+}
+
+fn step() -> impl Strategy<Value = Step> {
+    prop_oneof![
+        (0..20_000u64).prop_map(Step::Wait),
+        Just(Step::Feed),
+        (game(), any::<[u8; 32]>()).prop_map(|(game, random)| Step::Play(game, random)),
+        (0..600u32).prop_map(Step::Sleep),
+        Just(Step::Visit),
+    ]
+}
+
+fn mood() -> impl Strategy<Value = u64> {
+    0..=MAX_MOOD_VALUE
+}
+
+fn healthy_mood() -> impl Strategy<Value = u64> {
+    MOOD_LIMIT + 1..=MAX_MOOD_VALUE
+}
+
+fn event() -> impl Strategy<Value = SeasonalEvent> {
+    (0..2_000u64, 1..2_000u64, 0..400u64, 0..400u64, 0..400u64).prop_map(
+        |(start_block, blocks, hunger_percent, boredom_percent, energy_percent)| SeasonalEvent {
+            id: 0,
+            name: "event".into(),
+            start_block,
+            end_block: start_block + blocks,
+            modifiers: Modifiers {
+                hunger_percent,
+                boredom_percent,
+                energy_percent,
+                ..Default::default()
+            },
+        },
+    )
+}
+
+/// Up to two events one after the other, as the registry never lets them
+/// overlap.
+fn events() -> impl Strategy<Value = Vec<SeasonalEvent>> {
+    prop::collection::vec(event(), 0..2).prop_map(|events| {
+        events
+            .into_iter()
+            .enumerate()
+            .map(|(index, mut event)| {
+                event.start_block += index as u64 * 2_000;
+                event.end_block += index as u64 * 2_000;
+                event
+            })
+            .collect()
+    })
+}
+
+fn state(fed: u64, entertained: u64, rested: u64, block: u64) -> TamagotchiState {
+    TamagotchiState {
+        fed,
+        fed_block: block,
+        entertained,
+        entertained_block: block,
+        rested,
+        rested_block: block,
+        ..Default::default()
+    }
+}
+
+fn assert_in_bounds(tamagotchi: &TamagotchiState) {
+    for mood in [tamagotchi.fed, tamagotchi.entertained, tamagotchi.rested] {
+        assert!(mood <= MAX_MOOD_VALUE, "mood {} above maximum", mood);
+    }
+}
+
+proptest! {
+    #[test]
+    fn moods_stay_in_bounds(
+        (fed, entertained, rested) in (mood(), mood(), mood()),
+        start in 0..MAX_BLOCK,
+        steps in prop::collection::vec(step(), 0..64),
+    ) {
+        let mut tamagotchi = state(fed, entertained, rested, start);
+        let mut block = start;
+        for step in steps {
+            tamagotchi.update_mood(block);
+            // Rejected actions are fine, only the moods matter here
+            match step {
+                Step::Wait(blocks) => block += blocks,
+                Step::Feed => {
+                    let _ = tamagotchi.feed(block);
+                }
+                Step::Play(game, random) => {
+                    let _ = tamagotchi.play(game, random, block);
+                }
+                Step::Sleep(blocks) => {
+                    let _ = tamagotchi.sleep(blocks, block);
+                }
+                Step::Visit => {
+                    tamagotchi.enjoy_visit(1.into());
+                }
+            }
+            assert_in_bounds(&tamagotchi);
+        }
+        tamagotchi.update_mood(block);
+        assert_in_bounds(&tamagotchi);
+    }
+
+    #[test]
+    fn decay_saturates_at_zero(
+        (fed, entertained, rested) in (mood(), mood(), mood()),
+        start in 0..MAX_BLOCK,
+        elapsed in 0..MAX_BLOCK,
+    ) {
+        let mut tamagotchi = state(fed, entertained, rested, start);
+        tamagotchi.update_mood(start + elapsed);
+        prop_assert_eq!(tamagotchi.fed, fed.saturating_sub(HUNGER_PER_BLOCK * elapsed));
+        prop_assert_eq!(
+            tamagotchi.entertained,
+            entertained.saturating_sub(BOREDOM_PER_BLOCK * elapsed)
+        );
+        prop_assert_eq!(tamagotchi.rested, rested.saturating_sub(ENERGY_PER_BLOCK * elapsed));
+    }
+
+    #[test]
+    fn update_mood_is_idempotent(
+        (fed, entertained, rested) in (mood(), mood(), mood()),
+        start in 0..MAX_BLOCK,
+        elapsed in 0..100_000u64,
+        nap in 0..600u32,
+    ) {
+        let mut tamagotchi = state(fed, entertained, rested, start);
+        let _ = tamagotchi.sleep(nap, start);
+        tamagotchi.update_mood(start + elapsed);
+        let once = tamagotchi.encode();
+        tamagotchi.update_mood(start + elapsed);
+        prop_assert_eq!(once, tamagotchi.encode());
+    }
+
+    #[test]
+    fn earlier_block_does_not_underflow(
+        (fed, entertained, rested) in (mood(), mood(), mood()),
+        start in 1..MAX_BLOCK,
+        back in 1..MAX_BLOCK,
+    ) {
+        let mut tamagotchi = state(fed, entertained, rested, start);
+        tamagotchi.update_mood(start.saturating_sub(back));
+        prop_assert_eq!(
+            (tamagotchi.fed, tamagotchi.entertained, tamagotchi.rested),
+            (fed, entertained, rested)
+        );
+        prop_assert_eq!(tamagotchi.fed_block, start);
+    }
+
+    #[test]
+    fn neglect_starts_when_a_mood_reaches_the_limit(
+        (fed, entertained, rested) in (healthy_mood(), healthy_mood(), healthy_mood()),
+        events in events(),
+        elapsed in 1..10_000u64,
+        nap in 0..600u32,
+    ) {
+        let mut tamagotchi = state(fed, entertained, rested, 0);
+        tamagotchi.seasonal_events = events;
+        let _ = tamagotchi.sleep(nap, 0);
+        tamagotchi.update_mood(elapsed);
+        let above = [tamagotchi.fed, tamagotchi.entertained, tamagotchi.rested]
+            .into_iter()
+            .all(|mood| mood > MOOD_LIMIT);
+        prop_assert_eq!(above, tamagotchi.stats.neglected_blocks == 0);
+    }
+
+    #[test]
+    fn decay_does_not_depend_on_update_frequency(
+        (fed, entertained, rested) in (mood(), mood(), mood()),
+        events in events(),
+        nap in 0..600u32,
+        waits in prop::collection::vec(1..300u64, 1..32),
+    ) {
+        let mut stepped = state(fed, entertained, rested, 0);
+        stepped.seasonal_events = events;
+        let _ = stepped.sleep(nap, 0);
+        let mut at_once = state(fed, entertained, rested, 0);
+        at_once.seasonal_events = stepped.seasonal_events.clone();
+        at_once.sleeping_until = stepped.sleeping_until;
+        let mut block = 0;
+        for wait in waits {
+            block += wait;
+            stepped.update_mood(block);
+        }
+        at_once.update_mood(block);
+        prop_assert_eq!(
+            (stepped.fed, stepped.entertained, stepped.rested),
+            (at_once.fed, at_once.entertained, at_once.rested)
+        );
+    }
+}
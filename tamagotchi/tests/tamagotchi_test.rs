@@ -1,9 +1,15 @@
 use ft_main_io::InitFToken;
 use ft_main_io::*;
 use gstd::{prelude::*, ActorId};
-use gtest::{Log, Program, System};
+use gtest::{Log, Program, RunResult, System};
 use store_io::*;
-use tamagotchi_io::{TamagotchiState, TmAction, TmEvent};
+use tamagotchi_io::{
+    commitment_hash, insurance, BatchMode, Game, Hand, Household, Lifecycle, LoanTerms, Outcome,
+    PetChange, PetEvent, PetMetadata, Profile, RenameFee, SpendLimit, Stage, TamagotchiState,
+    TmAction, TmError, TmEvent, TmInit, Wellbeing, CHECK_INTERVAL, COMMIT_STAKE,
+    FILL_PER_ENTERTAINMENT, FILL_PER_FEED, HUNGER_PER_BLOCK, MAX_LOAN_BLOCKS, REST_PER_SLEEP_BLOCK,
+    REVEAL_BLOCKS,
+};
 
 //Codespaces only, replace with your source from dapps-smart-contract-academy/contracts/upload-contracts/programs/
 const FT_STORAGE_FILE: &str = "/workspaces/maskladevlu-gear-academy/contracts/ft_storage.opt.wasm";
@@ -29,16 +35,333 @@ fn tamagotchi_mood() {
     assert!(!res.main_failed());
     let log = Log::builder().dest(FERNANDO).payload(TmEvent::Fed);
     assert!(res.contains(&log));
-    let res = program.send(FERNANDO, TmAction::Sleep);
+    let res = program.send(FERNANDO, TmAction::Sleep { blocks: 10 });
     assert!(!res.main_failed());
     let log = Log::builder().dest(FERNANDO).payload(TmEvent::Slept);
     assert!(res.contains(&log));
-    let res = program.send(FERNANDO, TmAction::Play);
+    let res = program.send(FERNANDO, TmAction::Play(Game::Fetch));
     assert!(!res.main_failed());
     let log = Log::builder().dest(FERNANDO).payload(TmEvent::Entertained);
     assert!(res.contains(&log));
 }
 
+#[test]
+fn tamagotchi_stats() {
+    let sys = System::new();
+    let program = init_tamagotchi(&sys);
+    for action in [
+        TmAction::Feed,
+        TmAction::Feed,
+        TmAction::Play(Game::Fetch),
+        TmAction::Sleep { blocks: 10 },
+    ] {
+        let res = program.send(FERNANDO, action);
+        assert!(!res.main_failed());
+    }
+    let res = program.send(LUIS, TmAction::Stats);
+    assert!(!res.main_failed());
+    let stats = match last_event(&res) {
+        TmEvent::Stats(stats) => stats,
+        _ => panic!("Stats not received"),
+    };
+    assert_eq!((stats.feeds, stats.plays, stats.sleeps), (2, 1, 1));
+    assert!(stats.age_blocks >= 4);
+    //Moods start at the minimum, so every block so far was neglected
+    assert_eq!(stats.neglected_blocks, stats.age_blocks);
+    assert_eq!(stats.longest_streak, 0);
+    assert_eq!(stats.ft_spent, 0);
+}
+
+#[test]
+fn tamagotchi_profile() {
+    let sys = System::new();
+    let program = init_tamagotchi(&sys);
+    //Owner-only and validated rename
+    let res = program.send(LUIS, TmAction::Rename(String::from("Luis pet")));
+    assert!(res.main_failed());
+    let res = program.send(FERNANDO, TmAction::Rename(String::from(" <script>")));
+    assert!(!res.main_failed());
+    assert_eq!(last_event(&res), TmEvent::Error(TmError::InvalidName));
+    let res = program.send(FERNANDO, TmAction::Rename(String::from("Armando II")));
+    assert!(!res.main_failed());
+    let log = Log::builder()
+        .dest(FERNANDO)
+        .payload(TmEvent::Renamed(String::from("Armando II")));
+    assert!(res.contains(&log));
+    let profile = Profile {
+        avatar_uri: Some(String::from("ipfs://bafybeigdyrzt")),
+        description: String::from("Likes naps"),
+        tags: vec![String::from("sleepy"), String::from("gen-1")],
+    };
+    let res = program.send(
+        FERNANDO,
+        TmAction::SetProfile(Profile {
+            avatar_uri: Some(String::from("javascript:alert(1)")),
+            ..profile.clone()
+        }),
+    );
+    assert!(res.main_failed());
+    let res = program.send(FERNANDO, TmAction::SetProfile(profile.clone()));
+    assert!(!res.main_failed());
+    let res = program.send(LUIS, TmAction::Metadata);
+    let log = Log::builder()
+        .dest(LUIS)
+        .payload(TmEvent::Metadata(PetMetadata {
+            name: String::from("Armando II"),
+            profile,
+            stage: Stage::Baby,
+        }));
+    assert!(res.contains(&log));
+}
+
+#[test]
+fn tamagotchi_rename_fee() {
+    let sys = System::new();
+    let ft_main = init_ft_main(&sys);
+    let program = init_tamagotchi(&sys);
+    mint_tokens_for(&ft_main, TAMAGOTCHI.into(), 1000);
+    mint_tokens_for(&ft_main, FERNANDO.into(), 100);
+    for action in [
+        TmAction::SetTokenContract(FT_MAIN.into()),
+        TmAction::SetRenameFee(Some(RenameFee {
+            recipient: LUIS.into(),
+            amount: 100,
+        })),
+    ] {
+        let res = program.send(FERNANDO, action);
+        assert!(!res.main_failed());
+    }
+    //The renamer pays the fee, never the pet
+    let rename = TmAction::Rename(String::from("Armando II"));
+    let res = program.send(FERNANDO, rename.clone());
+    assert!(!res.main_failed());
+    assert_eq!(
+        last_event(&res),
+        TmEvent::Error(TmError::TokenTransferFailed)
+    );
+    approve_tokens_for(&ft_main, FERNANDO, TAMAGOTCHI.into(), 100, 0);
+    let res = program.send(FERNANDO, rename);
+    assert!(!res.main_failed());
+    check_balance(&ft_main, LUIS, 100);
+    check_balance(&ft_main, FERNANDO, 0);
+    check_balance(&ft_main, TAMAGOTCHI, 1000);
+    //A shared pet's fee needs the household to agree
+    let res = program.send(
+        FERNANDO,
+        TmAction::SetHousehold(Household {
+            members: vec![FERNANDO.into(), LUIS.into()],
+            threshold: 2,
+        }),
+    );
+    assert!(!res.main_failed());
+    let res = program.send(LUIS, TmAction::SetRenameFee(None));
+    assert!(res.main_failed());
+}
+
+#[test]
+fn tamagotchi_sleep() {
+    let sys = System::new();
+    let program = init_tamagotchi(&sys);
+    let res = program.send(FERNANDO, TmAction::Sleep { blocks: 0 });
+    assert!(res.main_failed());
+    let res = program.send(FERNANDO, TmAction::Sleep { blocks: 100 });
+    assert!(!res.main_failed());
+    let res = program.send(FERNANDO, TmAction::Sleep { blocks: 100 });
+    assert!(res.main_failed());
+    //Nobody else can end the nap
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    let until = state.sleeping_until.expect("Tamagotchi not asleep");
+    let res = program.send(FERNANDO, TmAction::WakeUp { until });
+    assert!(res.main_failed());
+    //Rest recovers while asleep
+    sys.spend_blocks(50);
+    let res = program.send(FERNANDO, TmAction::Name);
+    assert!(!res.main_failed());
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    assert!(state.rested >= 50 * REST_PER_SLEEP_BLOCK);
+    //Feeding wakes the pet up with a penalty
+    let rested = state.rested;
+    let res = program.send(FERNANDO, TmAction::Feed);
+    assert!(!res.main_failed());
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    assert_eq!(state.sleeping_until, None);
+    assert!(state.rested < rested);
+}
+
+#[test]
+fn tamagotchi_diminishing_returns() {
+    let sys = System::new();
+    let program = init_tamagotchi(&sys);
+    let mut fills = Vec::new();
+    for _ in 0..3 {
+        let before: TamagotchiState = program.read_state().expect("File reading state");
+        let res = program.send(FERNANDO, TmAction::Feed);
+        assert!(!res.main_failed());
+        let after: TamagotchiState = program.read_state().expect("File reading state");
+        fills.push(after.fed + HUNGER_PER_BLOCK - before.fed);
+    }
+    assert_eq!(
+        fills,
+        vec![FILL_PER_FEED, FILL_PER_FEED / 2, FILL_PER_FEED / 4]
+    );
+    //Repeating an action in the same block is on cooldown
+    let res = program.send(
+        FERNANDO,
+        TmAction::Batch {
+            actions: vec![TmAction::Play(Game::Fetch), TmAction::Play(Game::Fetch)],
+            mode: BatchMode::BestEffort,
+        },
+    );
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    let log = Log::builder()
+        .dest(FERNANDO)
+        .payload(TmEvent::BatchResult(vec![
+            Ok(TmEvent::Entertained),
+            Err(TmError::OnCooldown {
+                ready_at: state.entertained_block + 1,
+            }),
+        ]));
+    assert!(res.contains(&log));
+}
+
+#[test]
+fn tamagotchi_games() {
+    let sys = System::new();
+    let program = init_tamagotchi(&sys);
+    let res = program.send(FERNANDO, TmAction::Play(Game::GuessTheNumber(11)));
+    assert!(res.main_failed());
+    let before: TamagotchiState = program.read_state().expect("File reading state");
+    let res = program.send(
+        FERNANDO,
+        TmAction::Play(Game::RockPaperScissors(Hand::Rock)),
+    );
+    assert!(!res.main_failed());
+    let result = match last_event(&res) {
+        TmEvent::Played(result) => result,
+        _ => panic!("Game result not received"),
+    };
+    let expected = match result.pet {
+        Game::RockPaperScissors(Hand::Scissors) => Outcome::Win,
+        Game::RockPaperScissors(Hand::Paper) => Outcome::Loss,
+        Game::RockPaperScissors(Hand::Rock) => Outcome::Draw,
+        _ => panic!("Pet played another game"),
+    };
+    assert_eq!(result.outcome, expected);
+    assert_eq!(result.xp, expected.reward().xp);
+    let after: TamagotchiState = program.read_state().expect("File reading state");
+    assert_eq!(after.stats.xp, before.stats.xp + result.xp);
+    assert_eq!(
+        result.entertainment,
+        FILL_PER_ENTERTAINMENT * expected.reward().fill_percent / 100
+    );
+}
+
+#[test]
+fn tamagotchi_visit() {
+    let sys = System::new();
+    let program = init_tamagotchi(&sys);
+    let other_pet = Program::current_with_id(&sys, OTHER_PET);
+    let res = other_pet.send(
+        LUIS,
+        TmInit::New {
+            name: String::from("Luisito"),
+            guardian: None,
+        },
+    );
+    assert!(!res.main_failed());
+    //Visits need the host's owner to accept them
+    let res = program.send(
+        FERNANDO,
+        TmAction::Visit {
+            other_pet: OTHER_PET.into(),
+        },
+    );
+    assert!(!res.main_failed());
+    assert_eq!(last_event(&res), TmEvent::Error(TmError::VisitFailed));
+    let res = other_pet.send(
+        FERNANDO,
+        TmAction::AcceptVisit {
+            other_pet: TAMAGOTCHI.into(),
+        },
+    );
+    assert!(res.main_failed());
+    let res = other_pet.send(
+        LUIS,
+        TmAction::AcceptVisit {
+            other_pet: TAMAGOTCHI.into(),
+        },
+    );
+    assert!(!res.main_failed());
+    let before: TamagotchiState = other_pet.read_state().expect("File reading state");
+    let res = program.send(
+        FERNANDO,
+        TmAction::Visit {
+            other_pet: OTHER_PET.into(),
+        },
+    );
+    let log = Log::builder().dest(FERNANDO).payload(TmEvent::Visited {
+        other_pet: OTHER_PET.into(),
+        friendship: 1,
+    });
+    assert!(res.contains(&log));
+    let after: TamagotchiState = other_pet.read_state().expect("File reading state");
+    assert!(after.entertained > before.entertained);
+    assert!(after.accepted_visits.is_empty());
+    let res = other_pet.send(FERNANDO, TmAction::Friends);
+    assert_eq!(
+        last_event(&res),
+        TmEvent::Friends(vec![(TAMAGOTCHI.into(), 1)])
+    );
+}
+
+#[test]
+fn tamagotchi_wellbeing() {
+    let sys = System::new();
+    let program = init_tamagotchi(&sys);
+    let res = program.send(FERNANDO, TmAction::Feed);
+    assert!(!res.main_failed());
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    let wellbeing = Wellbeing::new(
+        (state.fed, state.entertained, state.rested),
+        state.stats.current_neglect,
+        0,
+    );
+    assert_eq!(state.wellbeing, wellbeing);
+    let res = program.send(LUIS, TmAction::Wellbeing);
+    assert!(!res.main_failed());
+    assert_eq!(last_event(&res), TmEvent::Wellbeing(wellbeing));
+    //Only attributes the pet bought can be equipped
+    let res = program.send(FERNANDO, TmAction::Equip(1));
+    assert!(res.main_failed());
+}
+
+#[test]
+fn tamagotchi_events() {
+    let sys = System::new();
+    let program = init_tamagotchi(&sys);
+    sys.spend_blocks(10);
+    let res = program.send(FERNANDO, TmAction::Feed);
+    assert!(!res.main_failed());
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    assert_eq!(
+        pet_events(&res),
+        vec![PetChange::Moods {
+            fed: state.fed,
+            entertained: state.entertained,
+            rested: state.rested,
+        }]
+    );
+    let res = program.send(FERNANDO, TmAction::Transfer(LUIS.into()));
+    assert!(!res.main_failed());
+    assert!(pet_events(&res).contains(&PetChange::HouseholdChanged(Household::solo(LUIS.into()))));
+    let res = program.send(LUIS, TmAction::Approve(FERNANDO.into()));
+    assert!(!res.main_failed());
+    assert_eq!(
+        pet_events(&res),
+        vec![PetChange::AllowedAccountChanged(Some(FERNANDO.into()))]
+    );
+}
+
 #[test]
 fn tamagotchi_nft() {
     let sys = System::new();
@@ -64,6 +387,207 @@ fn tamagotchi_nft() {
     assert!(res.main_failed());
 }
 
+#[test]
+fn tamagotchi_household() {
+    let sys = System::new();
+    let program = init_tamagotchi(&sys);
+    let household = Household {
+        members: vec![FERNANDO.into(), LUIS.into(), SERVICE_MASTER.into()],
+        threshold: 2,
+    };
+    let res = program.send(FERNANDO, TmAction::SetHousehold(household.clone()));
+    assert!(!res.main_failed());
+    //Care is open to every member
+    let res = program.send(SERVICE_MASTER, TmAction::Feed);
+    assert!(!res.main_failed());
+    //Guarded actions need proposals
+    let res = program.send(FERNANDO, TmAction::Transfer(LUIS.into()));
+    assert!(res.main_failed());
+    let set_token_contract = TmAction::SetTokenContract(FT_MAIN.into());
+    let res = program.send(FERNANDO, set_token_contract.clone());
+    assert!(res.main_failed());
+    let res = program.send(
+        FERNANDO,
+        TmAction::Propose {
+            action: Box::new(set_token_contract),
+            expires_at: 1000,
+        },
+    );
+    assert!(!res.main_failed());
+    let res = program.send(SERVICE_MASTER, TmAction::ApproveProposal(0));
+    assert_eq!(last_event(&res), TmEvent::TokenContractSet);
+    let res = program.send(
+        FERNANDO,
+        TmAction::Propose {
+            action: Box::new(TmAction::Transfer(LUIS.into())),
+            expires_at: 20,
+        },
+    );
+    assert_eq!(
+        last_event(&res),
+        TmEvent::ProposalApproved {
+            id: 1,
+            approvals: 1
+        }
+    );
+    sys.spend_blocks(20);
+    let res = program.send(LUIS, TmAction::ApproveProposal(1));
+    assert_eq!(last_event(&res), TmEvent::Error(TmError::ProposalExpired));
+    let res = program.send(
+        FERNANDO,
+        TmAction::Propose {
+            action: Box::new(TmAction::Transfer(LUIS.into())),
+            expires_at: 1000,
+        },
+    );
+    assert!(!res.main_failed());
+    let res = program.send(LUIS, TmAction::ApproveProposal(2));
+    assert_eq!(last_event(&res), TmEvent::Transfer(LUIS.into()));
+    let res = program.send(FERNANDO, TmAction::Household);
+    assert_eq!(
+        last_event(&res),
+        TmEvent::Household(Household::solo(LUIS.into()))
+    );
+}
+
+#[test]
+fn tamagotchi_sitter() {
+    let sys = System::new();
+    let program = init_tamagotchi(&sys);
+    let res = program.send(FERNANDO, TmAction::Approve(LUIS.into()));
+    assert!(!res.main_failed());
+    let res = program.send(LUIS, TmAction::Feed);
+    assert!(!res.main_failed());
+    //Sitters care for the pet but can't take it or its tokens
+    for action in [
+        TmAction::Transfer(LUIS.into()),
+        TmAction::SetHousehold(Household::solo(LUIS.into())),
+        TmAction::Fractionalise(vec![(LUIS.into(), 99), (FERNANDO.into(), 1)]),
+        TmAction::ApproveTokens {
+            account: LUIS.into(),
+            amount: 1000,
+        },
+        TmAction::SetTokenContract(LUIS.into()),
+        TmAction::SetRenameFee(None),
+        TmAction::Insure {
+            insurer: LUIS.into(),
+        },
+        TmAction::AcceptLoan {
+            lender: SERVICE_MASTER.into(),
+        },
+    ] {
+        let res = program.send(LUIS, action);
+        assert!(res.main_failed());
+    }
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    assert_eq!(state.household, Household::solo(FERNANDO.into()));
+    assert_eq!(state.shares, None);
+    assert_eq!(state.ft_contract, None);
+}
+
+#[test]
+fn tamagotchi_pause() {
+    let sys = System::new();
+    let program = init_tamagotchi(&sys);
+    let res = program.send(FERNANDO, TmAction::Pause { care_open: true });
+    assert!(res.main_failed());
+    let res = program.send(SERVICE_MASTER, TmAction::Pause { care_open: true });
+    assert!(!res.main_failed());
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    assert!(state.pause.is_some());
+    //Care stays open, moving the pet or tokens doesn't
+    let res = program.send(FERNANDO, TmAction::Feed);
+    assert!(!res.main_failed());
+    let res = program.send(FERNANDO, TmAction::Transfer(LUIS.into()));
+    assert!(res.main_failed());
+    let res = program.send(
+        FERNANDO,
+        TmAction::Batch {
+            actions: vec![TmAction::Play(Game::Fetch), TmAction::Withdraw],
+            mode: BatchMode::BestEffort,
+        },
+    );
+    assert!(res.main_failed());
+    let res = program.send(SERVICE_MASTER, TmAction::Pause { care_open: false });
+    assert!(!res.main_failed());
+    let res = program.send(FERNANDO, TmAction::Play(Game::Fetch));
+    assert!(res.main_failed());
+    let res = program.send(SERVICE_MASTER, TmAction::Unpause);
+    assert!(!res.main_failed());
+    let res = program.send(FERNANDO, TmAction::Transfer(LUIS.into()));
+    assert!(!res.main_failed());
+}
+
+#[test]
+fn tamagotchi_batch() {
+    let sys = System::new();
+    let program = init_tamagotchi(&sys);
+    let care = vec![
+        TmAction::Feed,
+        TmAction::Play(Game::Fetch),
+        TmAction::Sleep { blocks: 10 },
+    ];
+    let res = program.send(
+        FERNANDO,
+        TmAction::Batch {
+            actions: care.clone(),
+            mode: BatchMode::AllOrNothing,
+        },
+    );
+    assert!(!res.main_failed());
+    let log = Log::builder()
+        .dest(FERNANDO)
+        .payload(TmEvent::BatchResult(vec![
+            Ok(TmEvent::Fed),
+            Ok(TmEvent::Entertained),
+            Ok(TmEvent::Slept),
+        ]));
+    assert!(res.contains(&log));
+    //Permission is checked for every action
+    let mut owner_only = care;
+    owner_only.push(TmAction::RevokeApproval);
+    let res = program.send(
+        LUIS,
+        TmAction::Batch {
+            actions: owner_only.clone(),
+            mode: BatchMode::AllOrNothing,
+        },
+    );
+    assert!(res.main_failed());
+    let res = program.send(
+        FERNANDO,
+        TmAction::Batch {
+            actions: owner_only.clone(),
+            mode: BatchMode::AllOrNothing,
+        },
+    );
+    assert!(!res.main_failed());
+    //Async and scheduling actions are rejected
+    owner_only.push(TmAction::Withdraw);
+    let res = program.send(
+        FERNANDO,
+        TmAction::Batch {
+            actions: owner_only,
+            mode: BatchMode::AllOrNothing,
+        },
+    );
+    assert!(res.main_failed());
+    let res = program.send(
+        LUIS,
+        TmAction::Batch {
+            actions: vec![TmAction::Name, TmAction::Feed, TmAction::Withdraw],
+            mode: BatchMode::BestEffort,
+        },
+    );
+    assert!(!res.main_failed());
+    let log = Log::builder().dest(LUIS).payload(TmEvent::BatchResult(vec![
+        Ok(TmEvent::Name(String::from("Armando"))),
+        Err(TmError::NotPermitted),
+        Err(TmError::NotBatchable),
+    ]));
+    assert!(res.contains(&log));
+}
+
 #[test]
 fn tamagotchi_attribute_purchase() {
     let sys = System::new();
@@ -90,6 +614,14 @@ fn tamagotchi_attribute_purchase() {
         .dest(FERNANDO)
         .payload(TmEvent::TokenContractSet);
     assert!(res.contains(&log));
+    let res = tamagotchi.send(
+        FERNANDO,
+        TmAction::AllowStore {
+            store_id: ATTRIBUTE_STORE.into(),
+            limit: None,
+        },
+    );
+    assert!(!res.main_failed());
     //Expected flow
     println!("Approve tokens...");
     let res = tamagotchi.send(
@@ -124,6 +656,652 @@ fn tamagotchi_attribute_purchase() {
     println!("Attribute successfully bought...");
 }
 
+#[test]
+fn tamagotchi_store_whitelist() {
+    let sys = System::new();
+    let tamagotchi = init_tamagotchi(&sys);
+    let buy = TmAction::BuyAttribute {
+        store_id: ATTRIBUTE_STORE.into(),
+        attribute_id: 123,
+    };
+    //Store not allowed yet
+    let res = tamagotchi.send(FERNANDO, buy.clone());
+    assert!(res.main_failed());
+    //Only owner manages the list
+    let allow = TmAction::AllowStore {
+        store_id: ATTRIBUTE_STORE.into(),
+        limit: Some(SpendLimit {
+            amount: 500,
+            period: 100,
+        }),
+    };
+    let res = tamagotchi.send(LUIS, allow.clone());
+    assert!(res.main_failed());
+    let res = tamagotchi.send(FERNANDO, allow);
+    assert!(!res.main_failed());
+    let res = tamagotchi.send(FERNANDO, TmAction::AllowedStores);
+    let log = Log::builder()
+        .dest(FERNANDO)
+        .payload(TmEvent::AllowedStores(vec![(
+            ATTRIBUTE_STORE.into(),
+            Some(500),
+        )]));
+    assert!(res.contains(&log));
+    //Only owner and allowed account can buy
+    let res = tamagotchi.send(LUIS, buy);
+    assert!(res.main_failed());
+    //Approvals above the cap are rejected before reaching the FT contract
+    let res = tamagotchi.send(FERNANDO, TmAction::SetTokenContract(FT_MAIN.into()));
+    assert!(!res.main_failed());
+    let res = tamagotchi.send(
+        FERNANDO,
+        TmAction::ApproveTokens {
+            account: ATTRIBUTE_STORE.into(),
+            amount: 501,
+        },
+    );
+    assert!(!res.main_failed());
+    let log = Log::builder()
+        .dest(FERNANDO)
+        .payload(TmEvent::Error(TmError::StoreCapExceeded { remaining: 500 }));
+    assert!(res.contains(&log));
+}
+
+#[test]
+fn tamagotchi_delegate_budget() {
+    let sys = System::new();
+    let tamagotchi = init_tamagotchi(&sys);
+    let res = tamagotchi.send(FERNANDO, TmAction::Approve(LUIS.into()));
+    assert!(!res.main_failed());
+    let set_budget = TmAction::SetDelegateBudget {
+        delegate: LUIS.into(),
+        limit: Some(SpendLimit {
+            amount: 300,
+            period: 100,
+        }),
+    };
+    //A budget needs a period, and a bad one fails only its own batch item
+    let res = tamagotchi.send(
+        FERNANDO,
+        TmAction::Batch {
+            actions: vec![
+                TmAction::SetDelegateBudget {
+                    delegate: LUIS.into(),
+                    limit: Some(SpendLimit {
+                        amount: 300,
+                        period: 0,
+                    }),
+                },
+                TmAction::Feed,
+            ],
+            mode: BatchMode::BestEffort,
+        },
+    );
+    assert_eq!(
+        last_event(&res),
+        TmEvent::BatchResult(vec![Err(TmError::InvalidPeriod), Ok(TmEvent::Fed)])
+    );
+    //Delegates can't raise their own budget
+    let res = tamagotchi.send(LUIS, set_budget.clone());
+    assert!(res.main_failed());
+    let res = tamagotchi.send(FERNANDO, set_budget);
+    assert!(!res.main_failed());
+    let res = tamagotchi.send(LUIS, TmAction::DelegateBudget(LUIS.into()));
+    let log = Log::builder()
+        .dest(LUIS)
+        .payload(TmEvent::DelegateBudget(Some(300)));
+    assert!(res.contains(&log));
+    //Over budget purchases are rejected with a typed error
+    init_ft_main(&sys);
+    for action in [
+        TmAction::SetTokenContract(FT_MAIN.into()),
+        TmAction::AllowStore {
+            store_id: ATTRIBUTE_STORE.into(),
+            limit: None,
+        },
+        TmAction::ApproveTokens {
+            account: ATTRIBUTE_STORE.into(),
+            amount: 301,
+        },
+    ] {
+        let res = tamagotchi.send(FERNANDO, action);
+        assert!(!res.main_failed());
+    }
+    let res = tamagotchi.send(
+        LUIS,
+        TmAction::BuyAttribute {
+            store_id: ATTRIBUTE_STORE.into(),
+            attribute_id: 123,
+        },
+    );
+    assert!(!res.main_failed());
+    let log = Log::builder()
+        .dest(LUIS)
+        .payload(TmEvent::Error(TmError::BudgetExceeded { remaining: 300 }));
+    assert!(res.contains(&log));
+}
+
+#[test]
+fn tamagotchi_state_export_import() {
+    let sys = System::new();
+    let program = init_tamagotchi(&sys);
+    let res = program.send(FERNANDO, TmAction::Feed);
+    assert!(!res.main_failed());
+    let res = program.send(FERNANDO, TmAction::Sleep { blocks: 10 });
+    assert!(!res.main_failed());
+    //Only owner can export
+    let res = program.send(LUIS, TmAction::ExportState);
+    assert!(res.main_failed());
+    let res = program.send(FERNANDO, TmAction::ExportState);
+    assert!(!res.main_failed());
+    let envelope = match last_event(&res) {
+        TmEvent::StateExported(envelope) => envelope,
+        _ => panic!("State not exported"),
+    };
+    assert_eq!(envelope.version, tamagotchi_io::STATE_VERSION);
+    //Successor imports the exported pet
+    let successor = Program::current_with_id(&sys, SUCCESSOR);
+    let res = successor.send(TAMAGOTCHI, TmInit::Import(envelope));
+    assert!(!res.main_failed());
+    let old_state: TamagotchiState = program.read_state().expect("File reading state");
+    let new_state: TamagotchiState = successor.read_state().expect("File reading state");
+    assert_eq!(new_state.name, old_state.name);
+    assert_eq!(new_state.household, old_state.household);
+    assert_eq!(new_state.fed, old_state.fed);
+    assert_eq!(new_state.lifecycle, Lifecycle::Active);
+    assert_eq!(new_state.predecessor, Some(TAMAGOTCHI.into()));
+    //The successor wakes up from the nap it was imported in
+    assert!(new_state.sleeping_until.is_some());
+    sys.spend_blocks(10);
+    let new_state: TamagotchiState = successor.read_state().expect("File reading state");
+    assert_eq!(new_state.sleeping_until, None);
+}
+
+#[test]
+fn tamagotchi_failed_migration() {
+    let sys = System::new();
+    let program = init_tamagotchi(&sys);
+    sys.mint_to(FERNANDO, 10_000);
+    let feed = TmAction::Feed;
+    let salt = [3; 32];
+    let hash = commitment_hash(&FERNANDO.into(), &feed, &salt);
+    let res = program.send_with_value(FERNANDO, TmAction::Commit { hash }, COMMIT_STAKE);
+    assert!(!res.main_failed());
+    //A successor that can't import the state leaves the pet where it was,
+    //stakes included
+    let code_hash: [u8; 32] = sys.submit_code(FT_MAIN_FILE).into();
+    let res = program.send(
+        FERNANDO,
+        TmAction::Migrate {
+            new_program: code_hash.into(),
+        },
+    );
+    assert!(!res.main_failed());
+    assert_eq!(last_event(&res), TmEvent::Error(TmError::MigrationFailed));
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    assert_eq!(state.lifecycle, Lifecycle::Active);
+    assert_eq!(state.held_value(), COMMIT_STAKE);
+    let res = program.send(
+        FERNANDO,
+        TmAction::Reveal {
+            payload: Box::new(feed),
+            salt,
+        },
+    );
+    assert!(!res.main_failed());
+    assert_eq!(last_event(&res), TmEvent::Fed);
+}
+
+#[test]
+fn tamagotchi_native_value() {
+    let sys = System::new();
+    let program = init_tamagotchi(&sys);
+    sys.mint_to(LUIS, 10_000);
+    //Value attached to a free action is kept for the sender
+    let res = program.send_with_value(LUIS, TmAction::Name, 1_000);
+    assert!(!res.main_failed());
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    assert_eq!(state.owed.get(&ActorId::from(LUIS)), Some(&1_000));
+    let res = program.send(FERNANDO, TmAction::Withdraw);
+    assert!(res.main_failed());
+    let res = program.send(LUIS, TmAction::Withdraw);
+    assert!(!res.main_failed());
+    let log = Log::builder().dest(LUIS).payload(TmEvent::Withdrawn(1_000));
+    assert!(res.contains(&log));
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    assert!(state.owed.is_empty());
+    //Premiums can be paid in native value, and whatever isn't spent on them
+    //stays credited
+    sys.mint_to(FERNANDO, 10_000);
+    let insure = TmAction::Insure {
+        insurer: SERVICE_MASTER.into(),
+    };
+    let res = program.send_with_value(FERNANDO, insure.clone(), insurance::BASE_PREMIUM - 1);
+    assert!(!res.main_failed());
+    assert_eq!(last_event(&res), TmEvent::Error(TmError::InsufficientValue));
+    let res = program.send_with_value(FERNANDO, insure, insurance::BASE_PREMIUM + 500);
+    assert!(!res.main_failed());
+    assert!(matches!(last_event(&res), TmEvent::Insured { .. }));
+    let res = program.send_with_value(FERNANDO, TmAction::PayPremium, 1);
+    assert!(!res.main_failed());
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    assert_eq!(
+        state.owed.get(&ActorId::from(SERVICE_MASTER)),
+        Some(&(2 * insurance::BASE_PREMIUM))
+    );
+    assert_eq!(state.owed.get(&ActorId::from(FERNANDO)), Some(&500));
+}
+
+#[test]
+fn tamagotchi_native_purchase() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    let ft_main = init_ft_main(&sys);
+    let tamagotchi = init_tamagotchi(&sys);
+    sys.mint_to(LUIS, 10_000);
+    mint_tokens_for(&ft_main, TAMAGOTCHI.into(), 1000);
+    let attribute_id: AttributeId = 123;
+    let metadata = AttrMetadata {
+        title: String::from("Superbomba"),
+        description: String::from("Superbomba atribute"),
+        media: String::from("www.nowhere.com/Superbomba"),
+    };
+    create_attribute(&store, &metadata, 1000, attribute_id);
+    for action in [
+        TmAction::SetTokenContract(FT_MAIN.into()),
+        TmAction::AllowStore {
+            store_id: ATTRIBUTE_STORE.into(),
+            limit: None,
+        },
+        TmAction::Approve(LUIS.into()),
+        TmAction::SetDelegateBudget {
+            delegate: LUIS.into(),
+            limit: Some(SpendLimit {
+                amount: 0,
+                period: 100,
+            }),
+        },
+        TmAction::ApproveTokens {
+            account: ATTRIBUTE_STORE.into(),
+            amount: 1000,
+        },
+    ] {
+        let res = tamagotchi.send(FERNANDO, action);
+        assert!(!res.main_failed());
+    }
+    let buy = TmAction::BuyAttribute {
+        store_id: ATTRIBUTE_STORE.into(),
+        attribute_id,
+    };
+    //A delegate without budget can pay the household in native value
+    let res = tamagotchi.send(LUIS, buy.clone());
+    assert_eq!(
+        last_event(&res),
+        TmEvent::Error(TmError::BudgetExceeded { remaining: 0 })
+    );
+    let res = tamagotchi.send_with_value(LUIS, buy.clone(), 999);
+    assert_eq!(last_event(&res), TmEvent::Error(TmError::InsufficientValue));
+    let res = tamagotchi.send_with_value(LUIS, buy, 1);
+    assert!(!res.main_failed());
+    let state: TamagotchiState = tamagotchi.read_state().expect("File reading state");
+    assert!(state.attributes.contains(&attribute_id));
+    assert_eq!(state.owed.get(&ActorId::from(FERNANDO)), Some(&1000));
+    assert_eq!(state.owed.get(&ActorId::from(LUIS)), None);
+}
+
+#[test]
+fn tamagotchi_commit_reveal() {
+    let sys = System::new();
+    let program = init_tamagotchi(&sys);
+    sys.mint_to(LUIS, 10_000);
+    let res = program.send(FERNANDO, TmAction::Approve(LUIS.into()));
+    assert!(!res.main_failed());
+    let guess = TmAction::Play(Game::GuessTheNumber(3));
+    let salt = [7; 32];
+    let hash = commitment_hash(&LUIS.into(), &guess, &salt);
+    //Committing needs the stake attached
+    let res = program.send(LUIS, TmAction::Commit { hash });
+    assert!(res.main_failed());
+    let res = program.send_with_value(LUIS, TmAction::Commit { hash }, COMMIT_STAKE);
+    assert!(!res.main_failed());
+    assert!(matches!(last_event(&res), TmEvent::Committed { .. }));
+    //Only the committed action with its salt can be revealed
+    let res = program.send(
+        LUIS,
+        TmAction::Reveal {
+            payload: Box::new(TmAction::Play(Game::GuessTheNumber(4))),
+            salt,
+        },
+    );
+    assert!(res.main_failed());
+    let res = program.send(
+        LUIS,
+        TmAction::Reveal {
+            payload: Box::new(guess.clone()),
+            salt,
+        },
+    );
+    assert!(!res.main_failed());
+    assert!(matches!(last_event(&res), TmEvent::Played(_)));
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    assert!(state.commitments.is_empty());
+    assert_eq!(state.owed.get(&ActorId::from(LUIS)), Some(&COMMIT_STAKE));
+    //Unrevealed commitments forfeit the stake to the owner
+    let res = program.send(LUIS, TmAction::Commit { hash });
+    assert!(!res.main_failed());
+    sys.spend_blocks(REVEAL_BLOCKS as u32 + 1);
+    let res = program.send(
+        LUIS,
+        TmAction::Reveal {
+            payload: Box::new(guess),
+            salt,
+        },
+    );
+    assert!(res.main_failed());
+    let res = program.send(FERNANDO, TmAction::Name);
+    assert!(!res.main_failed());
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    assert!(state.commitments.is_empty());
+    assert_eq!(state.owed.get(&ActorId::from(LUIS)), None);
+    assert_eq!(
+        state.owed.get(&ActorId::from(FERNANDO)),
+        Some(&COMMIT_STAKE)
+    );
+    //Revealing an action that can't be batched still returns the stake
+    let visit = TmAction::Visit {
+        other_pet: OTHER_PET.into(),
+    };
+    let hash = commitment_hash(&LUIS.into(), &visit, &salt);
+    let res = program.send_with_value(LUIS, TmAction::Commit { hash }, COMMIT_STAKE);
+    assert!(!res.main_failed());
+    let res = program.send(
+        LUIS,
+        TmAction::Reveal {
+            payload: Box::new(visit),
+            salt,
+        },
+    );
+    assert!(!res.main_failed());
+    assert_eq!(last_event(&res), TmEvent::Error(TmError::NotBatchable));
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    assert!(state.commitments.is_empty());
+    assert_eq!(state.owed.get(&ActorId::from(LUIS)), Some(&COMMIT_STAKE));
+}
+
+#[test]
+fn tamagotchi_insurance() {
+    let sys = System::new();
+    let ft_main = init_ft_main(&sys);
+    let program = init_tamagotchi(&sys);
+    mint_tokens_for(&ft_main, TAMAGOTCHI.into(), 1000);
+    mint_tokens_for(&ft_main, FERNANDO.into(), 1000);
+    let res = program.send(FERNANDO, TmAction::SetTokenContract(FT_MAIN.into()));
+    assert!(!res.main_failed());
+    //Premiums need a policy
+    let res = program.send(FERNANDO, TmAction::PayPremium);
+    assert!(!res.main_failed());
+    assert_eq!(last_event(&res), TmEvent::Error(TmError::NotInsured));
+    let insure = TmAction::Insure {
+        insurer: SERVICE_MASTER.into(),
+    };
+    //The owner pays the premiums, not the pet
+    let res = program.send(FERNANDO, insure.clone());
+    assert!(!res.main_failed());
+    assert_eq!(
+        last_event(&res),
+        TmEvent::Error(TmError::TokenTransferFailed)
+    );
+    approve_tokens_for(&ft_main, FERNANDO, TAMAGOTCHI.into(), 1000, 0);
+    let res = program.send(FERNANDO, insure);
+    assert!(!res.main_failed());
+    assert!(matches!(last_event(&res), TmEvent::Insured { .. }));
+    //One more period can be paid ahead, but not two
+    let res = program.send(FERNANDO, TmAction::PayPremium);
+    assert!(!res.main_failed());
+    let res = program.send(FERNANDO, TmAction::PayPremium);
+    assert!(!res.main_failed());
+    assert_eq!(last_event(&res), TmEvent::Error(TmError::PremiumNotDue));
+    check_balance(&ft_main, SERVICE_MASTER, 2 * insurance::BASE_PREMIUM);
+    check_balance(&ft_main, FERNANDO, 1000 - 2 * insurance::BASE_PREMIUM);
+    check_balance(&ft_main, TAMAGOTCHI, 1000);
+    //Dying of neglect while insured revives the pet
+    let results = sys.spend_blocks(insurance::DEATH_NEGLECT_BLOCKS as u32 + CHECK_INTERVAL);
+    let revived = TmEvent::Revived {
+        revivals_left: insurance::POLICY_REVIVALS - 1,
+    };
+    assert!(results
+        .iter()
+        .flat_map(|res| res.log())
+        .any(|log| TmEvent::decode(&mut log.payload()).ok() == Some(revived.clone())));
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    assert!(state.is_active());
+    assert_eq!(
+        state.insurance.map(|policy| policy.revivals_left),
+        Some(insurance::POLICY_REVIVALS - 1)
+    );
+    //Neglect makes the next premium dearer
+    let res = program.send(FERNANDO, TmAction::Insurance);
+    assert!(!res.main_failed());
+    let premium = match last_event(&res) {
+        TmEvent::Insurance { premium, .. } => premium,
+        event => panic!("Unexpected reply {:?}", event),
+    };
+    assert!(premium > insurance::BASE_PREMIUM);
+    //Without premiums the policy lapses and the next death is final
+    sys.spend_blocks(2 * insurance::PREMIUM_PERIOD as u32);
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    assert!(state.insurance.is_none());
+    assert!(state.is_dead());
+    let res = program.send(FERNANDO, TmAction::Feed);
+    assert!(res.main_failed());
+}
+
+#[test]
+fn tamagotchi_loan() {
+    let sys = System::new();
+    let ft_main = init_ft_main(&sys);
+    let program = init_tamagotchi(&sys);
+    let res = program.send(FERNANDO, TmAction::SetTokenContract(FT_MAIN.into()));
+    assert!(!res.main_failed());
+    mint_tokens_for(&ft_main, LUIS.into(), 1000);
+    approve_tokens_for(&ft_main, LUIS, TAMAGOTCHI.into(), 1000, 0);
+    //Repaying less than the principal isn't a loan, nor is never repaying
+    for (repayment, duration) in [(900, 100), (1100, MAX_LOAN_BLOCKS + 1)] {
+        let res = program.send(
+            LUIS,
+            TmAction::OfferLoan(LoanTerms {
+                principal: 1000,
+                repayment,
+                duration,
+            }),
+        );
+        assert!(res.main_failed());
+    }
+    let terms = LoanTerms {
+        principal: 1000,
+        repayment: 1100,
+        duration: 100,
+    };
+    let res = program.send(LUIS, TmAction::OfferLoan(terms));
+    assert!(!res.main_failed());
+    //Only the owner can pledge the pet
+    let res = program.send(
+        LUIS,
+        TmAction::AcceptLoan {
+            lender: LUIS.into(),
+        },
+    );
+    assert!(res.main_failed());
+    let res = program.send(
+        FERNANDO,
+        TmAction::AcceptLoan {
+            lender: LUIS.into(),
+        },
+    );
+    assert!(!res.main_failed());
+    assert!(matches!(last_event(&res), TmEvent::LoanAccepted { .. }));
+    check_balance(&ft_main, FERNANDO, 1000);
+    //The pet is locked while the loan runs
+    let res = program.send(FERNANDO, TmAction::Transfer(OTHER_PET.into()));
+    assert!(res.main_failed());
+    //Repaying in time unlocks it and the due check changes nothing
+    let res = ft_main.send(
+        SERVICE_MASTER,
+        FTokenAction::Message {
+            transaction_id: TRANSACTION_ID + 1,
+            payload: LogicAction::Mint {
+                recipient: FERNANDO.into(),
+                amount: 100,
+            },
+        },
+    );
+    assert!(!res.main_failed());
+    approve_tokens_for(&ft_main, FERNANDO, TAMAGOTCHI.into(), 1100, 0);
+    let res = program.send(FERNANDO, TmAction::RepayLoan);
+    assert!(!res.main_failed());
+    assert_eq!(last_event(&res), TmEvent::LoanRepaid);
+    check_balance(&ft_main, LUIS, 1100);
+    sys.spend_blocks(terms.duration + 1);
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    assert!(state.loan.is_none());
+    assert_eq!(state.household, Household::solo(FERNANDO.into()));
+    //An unpaid loan hands the pet to the lender, not counting the blocks
+    //it couldn't be repaid in because the pet was paused
+    approve_tokens_for(&ft_main, LUIS, TAMAGOTCHI.into(), 1000, 1);
+    let res = program.send(LUIS, TmAction::OfferLoan(terms));
+    assert!(!res.main_failed());
+    let res = program.send(
+        FERNANDO,
+        TmAction::AcceptLoan {
+            lender: LUIS.into(),
+        },
+    );
+    let due_block = match last_event(&res) {
+        TmEvent::LoanAccepted { due_block, .. } => due_block,
+        event => panic!("Unexpected reply {:?}", event),
+    };
+    let res = program.send(SERVICE_MASTER, TmAction::Pause { care_open: true });
+    assert!(!res.main_failed());
+    sys.spend_blocks(terms.duration + 1);
+    let res = program.send(SERVICE_MASTER, TmAction::Unpause);
+    assert!(!res.main_failed());
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    let loan = state.loan.expect("Loan defaulted while paused");
+    assert!(loan.due_block > due_block + terms.duration as u64);
+    assert_eq!(state.household, Household::solo(FERNANDO.into()));
+    sys.spend_blocks(terms.duration + 1);
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    assert!(state.loan.is_none());
+    assert_eq!(state.household, Household::solo(LUIS.into()));
+    let res = program.send(FERNANDO, TmAction::RepayLoan);
+    assert_eq!(last_event(&res), TmEvent::Error(TmError::NoLoan));
+}
+
+#[test]
+fn tamagotchi_shares() {
+    let sys = System::new();
+    let ft_main = init_ft_main(&sys);
+    let program = init_tamagotchi(&sys);
+    mint_tokens_for(&ft_main, TAMAGOTCHI.into(), 1000);
+    mint_tokens_for(&ft_main, SERVICE_MASTER.into(), 1000);
+    let res = program.send(FERNANDO, TmAction::SetTokenContract(FT_MAIN.into()));
+    assert!(!res.main_failed());
+    let res = program.send(
+        FERNANDO,
+        TmAction::Fractionalise(vec![(FERNANDO.into(), 60), (LUIS.into(), 40)]),
+    );
+    assert!(!res.main_failed());
+    assert_eq!(last_event(&res), TmEvent::Fractionalised { total: 100 });
+    assert!(pet_events(&res).contains(&PetChange::SharesChanged(vec![
+        (FERNANDO.into(), 60),
+        (LUIS.into(), 40)
+    ])));
+    //The shareholders are the household, so both have to agree on guarded
+    //actions, and nobody can give the pet away
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    assert_eq!(
+        state.household,
+        Household {
+            members: vec![FERNANDO.into(), LUIS.into()],
+            threshold: 2,
+        }
+    );
+    let res = program.send(LUIS, TmAction::Feed);
+    assert!(!res.main_failed());
+    let res = program.send(FERNANDO, TmAction::SetRenameFee(None));
+    assert!(res.main_failed());
+    let res = program.send(FERNANDO, TmAction::Transfer(OTHER_PET.into()));
+    assert!(res.main_failed());
+    //Revenue is paid in by the sender and split by shares held, while the
+    //pet's own tokens stay with it
+    let revenue = TmAction::DistributeRevenue(1000);
+    let res = program.send(LUIS, revenue.clone());
+    assert!(!res.main_failed());
+    assert_eq!(
+        last_event(&res),
+        TmEvent::Error(TmError::TokenTransferFailed)
+    );
+    //A failed attempt doesn't hold the shares in place
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    assert!(state.shares.is_some_and(|shares| !shares.distributing));
+    approve_tokens_for(&ft_main, SERVICE_MASTER, TAMAGOTCHI.into(), 1000, 1);
+    let res = program.send(SERVICE_MASTER, revenue);
+    assert!(!res.main_failed());
+    assert_eq!(
+        last_event(&res),
+        TmEvent::RevenueDistributed {
+            amount: 1000,
+            unpaid: vec![],
+        }
+    );
+    check_balance(&ft_main, FERNANDO, 600);
+    check_balance(&ft_main, LUIS, 400);
+    check_balance(&ft_main, TAMAGOTCHI, 1000);
+    //Everyone was paid, so there's nothing left to claim
+    let res = program.send(LUIS, TmAction::ClaimTokens);
+    assert!(!res.main_failed());
+    assert_eq!(last_event(&res), TmEvent::Error(TmError::NothingToClaim));
+    //A buyout passes once its voters hold a majority
+    approve_tokens_for(&ft_main, LUIS, TAMAGOTCHI.into(), 300, 0);
+    let res = program.send(LUIS, TmAction::OfferBuyout { price_per_share: 5 });
+    assert!(!res.main_failed());
+    let res = program.send(LUIS, TmAction::VoteBuyout);
+    assert!(!res.main_failed());
+    assert_eq!(last_event(&res), TmEvent::BuyoutVoted { votes: 40 });
+    let res = program.send(FERNANDO, TmAction::VoteBuyout);
+    assert!(!res.main_failed());
+    assert_eq!(
+        last_event(&res),
+        TmEvent::BoughtOut {
+            buyer: LUIS.into(),
+            unpaid: vec![],
+        }
+    );
+    check_balance(&ft_main, FERNANDO, 900);
+    check_balance(&ft_main, LUIS, 100);
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    assert!(state.shares.is_none());
+    assert_eq!(state.household, Household::solo(LUIS.into()));
+    assert!(pet_events(&res).contains(&PetChange::SharesChanged(vec![])));
+    //Gathering every share also makes a sole owner
+    let res = program.send(
+        LUIS,
+        TmAction::Fractionalise(vec![(LUIS.into(), 1), (FERNANDO.into(), 1)]),
+    );
+    assert!(!res.main_failed());
+    let res = program.send(
+        FERNANDO,
+        TmAction::TransferShares {
+            to: LUIS.into(),
+            amount: 1,
+        },
+    );
+    assert!(!res.main_failed());
+    let state: TamagotchiState = program.read_state().expect("File reading state");
+    assert!(state.shares.is_none());
+    assert_eq!(state.household, Household::solo(LUIS.into()));
+}
+
 const FERNANDO: u64 = 100;
 const LUIS: u64 = 101;
 const SERVICE_MASTER: u64 = 6;
@@ -133,17 +1311,47 @@ const FT_STORAGE: u64 = 2;
 const FT_LOGIC: u64 = 3;
 const FT_MAIN: u64 = 4;
 const ATTRIBUTE_STORE: u64 = 5;
+const SUCCESSOR: u64 = 7;
+const OTHER_PET: u64 = 8;
 
 const TRANSACTION_ID: u64 = 0;
 
 fn init_tamagotchi(sys: &System) -> Program {
     let program = Program::current_with_id(&sys, TAMAGOTCHI);
-    let res = program.send(FERNANDO, String::from("Armando"));
+    let res = program.send(
+        FERNANDO,
+        TmInit::New {
+            name: String::from("Armando"),
+            guardian: Some(SERVICE_MASTER.into()),
+        },
+    );
     assert!(!res.main_failed());
     // println!("Successfully Tamagotchi contract loaded: {:?}", res);
     program
 }
 
+fn last_event(res: &RunResult) -> TmEvent {
+    let log = res
+        .log()
+        .iter()
+        .rev()
+        .find(|log| log.destination() != ActorId::zero())
+        .expect("No reply received");
+    TmEvent::decode(&mut log.payload()).expect("Unexpected reply payload")
+}
+
+fn pet_events(res: &RunResult) -> Vec<PetChange> {
+    res.log()
+        .iter()
+        .filter(|log| log.destination() == ActorId::zero())
+        .map(|log| {
+            PetEvent::decode(&mut log.payload())
+                .expect("Unexpected event payload")
+                .change
+        })
+        .collect()
+}
+
 fn init_store(sys: &System) -> Program {
     let program = Program::from_file_with_id(&sys, ATTRIBUTE_STORE, STORE_FILE);
     let res = program.send::<_, ActorId>(SERVICE_MASTER, FT_MAIN.into());
@@ -181,6 +1389,27 @@ fn mint_tokens_for(ft_program: &Program, recipient: ActorId, amount: u128) {
     assert!(result.contains(&log));
 }
 
+fn approve_tokens_for(
+    ft_program: &Program,
+    owner: u64,
+    approved_account: ActorId,
+    amount: u128,
+    transaction_id: u64,
+) {
+    let res = ft_program.send(
+        owner,
+        FTokenAction::Message {
+            transaction_id,
+            payload: LogicAction::Approve {
+                approved_account,
+                amount,
+            },
+        },
+    );
+    assert!(!res.main_failed());
+    assert!(res.contains(&Log::builder().dest(owner).payload(FTokenEvent::Ok)));
+}
+
 fn check_balance(ft_program: &Program, account: impl Into<ActorId>, expected_amount: u128) {
     let res = ft_program.send(SERVICE_MASTER, FTokenAction::GetBalance(account.into()));
     let payload = Log::builder()